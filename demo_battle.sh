@@ -26,9 +26,13 @@ send_battle_event() {
     local ai_name=$2
     local data=$3
     
-    # This would normally be sent through the WebSocket
-    # For demo purposes, we'll show what would be sent
+    # POST /battle/events takes the event type and AI name alongside the event's own fields,
+    # puts the commentary on the live feed and answers with it
     echo "📡 Sending battle event: $event_type for $ai_name"
+    curl -s -X POST http://localhost:8420/battle/events \
+        -H "Content-Type: application/json" \
+        -d "{\"type\": \"$event_type\", \"ai_name\": \"$ai_name\", ${data#\{}"
+    echo
 }
 
 echo "=== BATTLE BEGINS ==="
@@ -65,7 +69,7 @@ send_battle_event "TestAttempt" "Claude" '{"test_name": "memory_management_test"
 sleep 2
 
 # Battle result
-send_battle_event "BattleResult" "Claude" '{"winner": "Claude", "decisive_factor": "GPU-accelerated memory management", "duration": 180}'
+send_battle_event "BattleResult" "Claude" '{"winner": "Claude", "decisive_factor": "GPU-accelerated memory management", "duration_secs": 180}'
 
 echo
 echo "=== BATTLE COMPLETE ==="
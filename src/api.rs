@@ -4,12 +4,12 @@
 //!
 use actix_web::{web, HttpResponse, Responder, HttpRequest, Error};
 use actix_web::web::Bytes;
-use actix_web::http::header::ContentType;
+use actix_web::http::header::{self, ContentType};
+use actix_web::http::StatusCode;
 use actix_multipart::Multipart;
 use futures_util::StreamExt as _;
 use std::fs::File;
 use std::io::Write;
-use std::path::Path;
 use std::sync::{Arc, Mutex};
 use actix_web::rt::task;
 use actix_web::web::Data;
@@ -17,20 +17,31 @@ use actix_web::HttpResponseBuilder;
 use actix_web::HttpResponse as Resp;
 use actix_web::rt::time::interval;
 use std::time::Duration;
+use std::future::Future;
 use actix_web::web::Payload;
 use actix_web::HttpRequest as Req;
-use std::collections::VecDeque;
+use std::collections::HashMap;
 use base64::{Engine as _, engine::general_purpose};
 use serde::{Serialize, Deserialize};
 use std::time::SystemTime;
 use futures::StreamExt;
+use tokio::sync::broadcast::error::RecvError;
 
-use crate::markqant::Marqant;
-use crate::m8::{M8Container, M8Nexus};
-use crate::auctioneer::{Auctioneer, AuctionEvent, CommentaryStyle};
+use crate::error::ApiError;
+use crate::events::{NexusEvent, SharedEventHub, HEARTBEAT_INTERVAL};
+use crate::hex;
+use crate::idempotency::{IdempotencyCache, IdempotencyKey, Remembered, SharedIdempotencyCache, REPLAYED_HEADER};
+use crate::m8::{
+    M8Container, M8ContentType, M8Cursor, M8Nexus, M8Query, M8Sort, M8TypeStats, WaveComponent, MAX_PAYLOAD_SIZE, PHASH_KEY, SYSTEM_METADATA_KEYS, WAVE_MAGIC,
+    decode_wave_payload, emotional_bytes_to_vad, emotional_context_to_bytes, encode_wave_payload,
+};
+use crate::ingest::{IngestError, IngestRequest, IngestSource, SharedIngestor};
+use crate::jobs::{Job, JobHandle, SharedJobQueue};
+use crate::live_feed::SharedLiveFeed;
+use crate::retention::SharedRetentionPolicy;
+use crate::auctioneer::{AuctionEvent, Auctioneer, CommentaryStyle, LiveFeedMessage};
+use crate::auctioneer_battles::{get_crowd_reaction, BattleEventType};
 
-/// SSE event queue (shared across handlers)
-pub type EventQueue = Arc<Mutex<VecDeque<String>>>;
 
 #[derive(Serialize, Deserialize)]
 pub struct UploadResponse {
@@ -40,416 +51,1917 @@ pub struct UploadResponse {
     pub memory_ids: Vec<u64>,
     pub compression_ratio: Option<f64>,
     pub message: String,
+    #[serde(default)]
+    pub duplicate: bool, // True when the content was already stored
+}
+
+/// Response for an upload carrying several files (or `?bind=true`)
+#[derive(Serialize, Deserialize)]
+pub struct MultiUploadResponse {
+    pub success: bool, // True when every file was stored
+    pub files: Vec<UploadResponse>, // One result per multipart field, in order
+    pub compound: Option<UploadResponse>, // Compound binding the files, with ?bind=true
+    pub message: String,
+}
+
+/// Query parameters shared by the upload endpoints
+#[derive(Deserialize, Default, Clone)]
+pub struct UploadParams {
+    /// Comma-separated wave signatures of the container(s) this upload revises
+    pub parent: Option<String>,
+    /// Time-to-live in seconds, overriding the content-type retention policy
+    pub ttl: Option<u64>,
+    /// Explicit emotional context in [-1, 1], overriding the inferred one per axis
+    pub valence: Option<f32>,
+    pub arousal: Option<f32>,
+    pub dominance: Option<f32>,
+    /// Bind every file of a multipart upload into one compound container
+    #[serde(default)]
+    pub bind: bool,
+    /// Queue the upload as a background job and answer 202 with its ID
+    #[serde(default, rename = "async")]
+    pub run_async: bool,
+}
+
+impl UploadParams {
+    /// Overwrite whichever emotional axes the client set explicitly
+    pub fn apply_emotion(&self, container: &mut M8Container) {
+        if self.valence.is_none() && self.arousal.is_none() && self.dominance.is_none() {
+            return;
+        }
+        let [valence, arousal, dominance] = emotional_bytes_to_vad(&container.header.emotional_context);
+        let explicit = EmotionInput {
+            valence: self.valence.unwrap_or(valence),
+            arousal: self.arousal.unwrap_or(arousal),
+            dominance: self.dominance.unwrap_or(dominance),
+        };
+        container.header.emotional_context = emotional_context_to_bytes(&explicit.to_context());
+    }
+}
+
+/// Emotional context supplied by API clients, each axis in [-1, 1]
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+pub struct EmotionInput {
+    #[serde(default)]
+    pub valence: f32,
+    #[serde(default)]
+    pub arousal: f32,
+    #[serde(default)]
+    pub dominance: f32,
+}
+
+impl EmotionInput {
+    pub fn to_context(self) -> mem8::EmotionalContext {
+        mem8::EmotionalContext {
+            valence: self.valence.clamp(-1.0, 1.0),
+            arousal: self.arousal.clamp(-1.0, 1.0),
+            dominance: self.dominance.clamp(-1.0, 1.0),
+        }
+    }
+}
+
+/// Body for POST /bind
+#[derive(Serialize, Deserialize)]
+pub struct BindRequest {
+    /// Containers whose memories should be bound together
+    #[serde(default)]
+    pub signatures: Vec<String>,
+    /// Additional MEM8 memory IDs to bind directly
+    #[serde(default)]
+    pub memory_ids: Vec<u64>,
+    #[serde(default)]
+    pub emotion: EmotionInput,
+}
+
+/// Query parameters for POST /bind. The emotion comes from the body only.
+#[derive(Deserialize, Default)]
+pub struct BindParams {
+    /// Time-to-live in seconds, overriding the compound retention policy
+    pub ttl: Option<u64>,
+    /// Queue the bind as a background job and answer 202 with its ID
+    #[serde(default, rename = "async")]
+    pub run_async: bool,
+}
+
+/// One line of a POST /upload/batch body
+#[derive(Deserialize)]
+pub struct BatchRecord {
+    /// Plain text content
+    pub text: Option<String>,
+    /// Base64 content: `.mq`, `.m8` or anything else `/upload` accepts
+    pub data: Option<String>,
+    /// Filename hint for sniffing `data`
+    pub filename: Option<String>,
+    #[serde(default = "default_importance")]
+    pub importance: u8,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Per-record overrides of the batch's query parameters
+    pub parent: Option<String>,
+    pub ttl: Option<u64>,
+}
+
+/// One line of the POST /upload/batch response
+#[derive(Serialize)]
+pub struct BatchResult {
+    pub line: usize,
+    #[serde(flatten)]
+    pub result: UploadResponse,
+}
+
+/// JSON body for POST /upload/wave
+#[derive(Deserialize)]
+pub struct WaveUpload {
+    pub waves: Vec<WaveComponent>,
+    #[serde(default = "default_importance")]
+    pub importance: u8,
+}
+
+fn default_importance() -> u8 {
+    5
+}
+
+/// Query parameters for GET /container/{signature}
+#[derive(Deserialize)]
+pub struct ContainerQuery {
+    /// `m8`, `mq`, `markdown`, `json` or `content`; overrides the Accept header
+    pub format: Option<String>,
+}
+
+/// Query parameters for GET /containers
+#[derive(Deserialize)]
+pub struct ContainersQuery {
+    /// `time` (default) or `size`
+    pub sort: Option<String>,
+    /// `desc` (default, newest or largest first) or `asc`
+    pub order: Option<String>,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Page size, default 100, at most 1000
+    pub limit: Option<usize>,
+    /// Content type name, e.g. `Language` or `Visual`
+    pub content_type: Option<String>,
+    /// Time window, RFC 3339 (UTC) or unix seconds; `until` is exclusive
+    pub since: Option<String>,
+    pub until: Option<String>,
+    /// `key` (must exist) or `key:value`
+    pub meta: Option<String>,
+    /// One of the comma-separated `tags`
+    pub tag: Option<String>,
+}
+
+/// Default and largest page size for GET /containers
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+impl ContainersQuery {
+    /// Validate the parameters into a nexus query
+    fn to_query(&self) -> Result<M8Query, ApiError> {
+        let sort = match self.sort.as_deref() {
+            None | Some("time") => M8Sort::Time,
+            Some("size") => M8Sort::Size,
+            Some(other) => return Err(invalid_query(format!("Unknown sort: {} (expected time or size)", other))),
+        };
+        let descending = match self.order.as_deref() {
+            None | Some("desc") => true,
+            Some("asc") => false,
+            Some(other) => return Err(invalid_query(format!("Unknown order: {} (expected asc or desc)", other))),
+        };
+        let after = match self.cursor.as_deref() {
+            Some(cursor) => Some(decode_cursor(cursor, sort).ok_or_else(|| invalid_query(format!("Invalid cursor: {}", cursor)))?),
+            None => None,
+        };
+        let metadata = self.meta.iter()
+            .map(|meta| match meta.split_once(':') {
+                Some((key, value)) => (key.to_string(), Some(value.to_string())),
+                None => (meta.clone(), None),
+            })
+            .collect();
+        
+        Ok(M8Query {
+            sort,
+            descending,
+            after,
+            limit: self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+            content_type: self.content_type.clone(),
+            since: self.since.as_deref().map(parse_time).transpose().map_err(invalid_query)?,
+            until: self.until.as_deref().map(parse_time).transpose().map_err(invalid_query)?,
+            metadata,
+            tag: self.tag.clone(),
+        })
+    }
+}
+
+fn invalid_query(message: String) -> ApiError {
+    ApiError::bad_request("invalid_query", message)
+}
+
+/// Opaque page cursor: sort letter, sort value and signature, all hex
+fn encode_cursor(sort: M8Sort, (value, signature): M8Cursor) -> String {
+    let prefix = match sort {
+        M8Sort::Time => 't',
+        M8Sort::Size => 's',
+    };
+    format!("{}{:016x}{}", prefix, value, hex::encode(signature))
+}
+
+fn decode_cursor(cursor: &str, sort: M8Sort) -> Option<M8Cursor> {
+    let rest = cursor.strip_prefix(match sort {
+        M8Sort::Time => 't',
+        M8Sort::Size => 's',
+    })?;
+    let value = u64::from_str_radix(rest.get(..16)?, 16).ok()?;
+    Some((value, parse_signature(rest.get(16..)?)?))
+}
+
+/// RFC 3339 (UTC) or unix seconds
+fn parse_time(raw: &str) -> Result<SystemTime, String> {
+    if let Ok(secs) = raw.parse::<u64>() {
+        return std::time::UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+            .ok_or_else(|| format!("Invalid time {}: out of range", raw));
+    }
+    humantime::parse_rfc3339(raw).map_err(|e| format!("Invalid time {}: {}", raw, e))
+}
+
+/// One container in GET /containers
+#[derive(Serialize)]
+pub struct ContainerSummary {
+    pub signature: String,
+    pub content_type: String,
+    pub timestamp: String, // RFC 3339
+    pub size: usize, // Bytes in the data section, as stored
+    pub metadata: HashMap<String, String>,
+}
+
+impl ContainerSummary {
+    pub fn of(container: &M8Container) -> Self {
+        Self {
+            signature: hex::encode(container.wave_signature),
+            content_type: format!("{:?}", container.header.content_type),
+            timestamp: humantime::format_rfc3339_millis(container.header.timestamp).to_string(),
+            size: container.data.len(),
+            metadata: container.header.metadata.clone(),
+        }
+    }
+}
+
+/// Body of GET /containers
+#[derive(Serialize)]
+pub struct ContainerList {
+    pub containers: Vec<ContainerSummary>,
+    pub next_cursor: Option<String>, // Pass as ?cursor= for the next page
+}
+
+/// Header of a stored container, as served over HTTP
+#[derive(Serialize)]
+pub struct ContainerHeader {
+    pub signature: String,
+    pub content_type: String,
+    pub version: u8,
+    pub timestamp: u64, // Unix seconds
+    pub memory_ids: Vec<u64>,
+    pub emotion: EmotionInput,
+    pub metadata: HashMap<String, String>,
+    pub parents: Vec<String>,
+    pub compressed: bool,
+}
+
+impl ContainerHeader {
+    pub fn of(container: &M8Container) -> Self {
+        let header = &container.header;
+        let [valence, arousal, dominance] = emotional_bytes_to_vad(&header.emotional_context);
+        Self {
+            signature: hex::encode(container.wave_signature),
+            content_type: format!("{:?}", header.content_type),
+            version: header.version,
+            timestamp: header.timestamp.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+            memory_ids: header.memory_ids.clone(),
+            emotion: EmotionInput { valence, arousal, dominance },
+            metadata: header.metadata.clone(),
+            parents: header.parents.iter().map(hex::encode).collect(),
+            compressed: header.compressed,
+        }
+    }
+}
+
+/// Body of GET/PATCH /container/{signature}/meta
+#[derive(Serialize)]
+pub struct ContainerMeta {
+    #[serde(flatten)]
+    pub header: ContainerHeader,
+    pub data_size: usize, // Bytes in the data section, as stored
+    pub payload_size: usize, // Bytes of the plain, uncompressed payload
+    pub file_size: usize, // Bytes of the whole .m8 file
+}
+
+impl ContainerMeta {
+    pub fn of(container: &M8Container) -> anyhow::Result<Self> {
+        Ok(Self {
+            header: ContainerHeader::of(container),
+            data_size: container.data.len(),
+            payload_size: container.payload()?.len(),
+            file_size: container.to_bytes()?.len(),
+        })
+    }
+}
+
+/// Body of GET /container/{signature}?format=json
+#[derive(Serialize)]
+pub struct ContainerJson {
+    #[serde(flatten)]
+    pub header: ContainerHeader,
+    /// Text, markdown or a description of the content
+    pub content: String,
+    /// Base64 of the original bytes, for images, audio and blobs
+    pub data: Option<String>,
+}
+
+/// Representations of a container that GET /container/{signature} can serve
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ContainerFormat {
+    Content,
+    M8,
+    Marqant,
+    Markdown,
+    Json,
+}
+
+impl ContainerFormat {
+    /// `?format=` wins; otherwise the first Accept type we know; otherwise the content
+    fn negotiate(req: &HttpRequest, format: Option<&str>) -> Result<Self, ApiError> {
+        if let Some(format) = format {
+            return match format {
+                "content" => Ok(Self::Content),
+                "m8" => Ok(Self::M8),
+                "mq" | "marqant" => Ok(Self::Marqant),
+                "markdown" | "md" => Ok(Self::Markdown),
+                "json" => Ok(Self::Json),
+                other => Err(ApiError::bad_request(
+                    "invalid_format",
+                    format!("Unknown format: {} (expected m8, mq, markdown, json or content)", other),
+                )),
+            };
+        }
+        
+        let accept = req.headers().get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .unwrap_or_default();
+        let format = accept.split(',')
+            .map(|media| media.split(';').next().unwrap_or_default().trim())
+            .find_map(|media| match media {
+                crate::mime::M8_MIME => Some(Self::M8),
+                crate::mime::MARQANT_MIME => Some(Self::Marqant),
+                "text/markdown" => Some(Self::Markdown),
+                "application/json" => Some(Self::Json),
+                _ => None,
+            });
+        Ok(format.unwrap_or(Self::Content))
+    }
+}
+
+/// Query parameters for GET /container/{signature}/wave
+#[derive(Deserialize)]
+pub struct WaveQuery {
+    /// `raw` returns the binary M8WV payload instead of JSON
+    pub format: Option<String>,
+}
+
+/// Query parameters for GET /visual/similar/{signature}
+#[derive(Deserialize)]
+pub struct SimilarityQuery {
+    /// Maximum Hamming distance between perceptual hashes (0-64)
+    pub max_distance: Option<u32>,
+}
+
+/// Query parameters for GET /retention
+#[derive(Deserialize)]
+pub struct RetentionQuery {
+    pub limit: Option<usize>,
+}
+
+/// Body for PUT /retention/policy
+#[derive(Deserialize)]
+pub struct RetentionRule {
+    pub content_type: String,
+    /// TTL in seconds; omit or null to keep this content type forever
+    pub ttl: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct NexusStats {
     pub total_containers: usize,
     pub type_counts: std::collections::HashMap<String, usize>,
+    pub type_stats: HashMap<String, M8TypeStats>, // Count and byte totals per content type
+    pub compression: CompressionStats,
     pub mem8_stats: Mem8Stats,
 }
 
+/// What compression saved across every stored container
+#[derive(Serialize, Deserialize)]
+pub struct CompressionStats {
+    pub original_bytes: u64,
+    pub stored_bytes: u64,
+    pub saved_bytes: u64,
+    pub ratio: f64, // original / stored
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Mem8Stats {
     pub total_memories: u64,
-    pub grid_dimensions: (usize, usize),
+    pub referenced_memories: usize, // Memories some container still uses
+    pub recent_memories: usize, // Stored within the last hour
+}
+
+/// Query parameters of GET /mem8/memories
+#[derive(Deserialize)]
+pub struct MemoriesQuery {
+    pub since: Option<String>, // Unix seconds or RFC 3339; defaults to an hour ago
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// One memory in GET /mem8/memories
+#[derive(Serialize)]
+pub struct MemorySummary {
+    pub id: u64,
+    pub content: String,
+    pub timestamp: String, // RFC 3339
+    pub containers: Vec<String>, // Signatures of the containers referencing it
+}
+
+/// Body of GET /mem8/memory/{id}
+#[derive(Serialize)]
+pub struct MemoryDetail {
+    pub id: u64,
+    pub content: Option<String>,
+    pub importance: Option<u8>, // As the nexus handed it to MEM8
+    pub wave: Option<WaveComponent>,
+    pub timestamp: Option<String>, // RFC 3339
+    pub containers: Vec<ContainerSummary>,
+}
+
+/// Body of GET /mem8/context/latest
+#[derive(Serialize)]
+pub struct LatestMemory {
+    pub id: u64,
+    pub content: String,
+    pub timestamp: String, // RFC 3339
+    pub containers: Vec<ContainerSummary>, // Containers referencing the memory
 }
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg
+        // Extractor failures use the same JSON error body as the handlers
+        .app_data(web::QueryConfig::default()
+            .error_handler(|e, _| ApiError::bad_request("invalid_query", e.to_string()).into()))
+        .app_data(web::PathConfig::default()
+            .error_handler(|e, _| ApiError::bad_request("invalid_path", e.to_string()).into()))
+        .app_data(web::JsonConfig::default()
+            .error_handler(|e, _| ApiError::new(actix_web::ResponseError::status_code(&e), "invalid_json", e.to_string()).into()))
+        
         // Upload endpoints
         .service(web::resource("/upload").route(web::post().to(upload_handler)))
         .service(web::resource("/upload/marqant").route(web::post().to(upload_marqant)))
         .service(web::resource("/upload/text").route(web::post().to(upload_text)))
+        .service(web::resource("/upload/wave").route(web::post().to(upload_wave)))
+        .service(web::resource("/upload/batch").route(web::post().to(upload_batch)))
+        .service(web::resource("/jobs/{id}").route(web::get().to(get_job)))
         
         // Retrieval endpoints
-        .service(web::resource("/container/{signature}").route(web::get().to(get_container)))
+        .service(web::resource("/container/{signature}")
+            .route(web::get().to(get_container))
+            .route(web::delete().to(delete_container)))
+        .service(web::resource("/container/{signature}/ancestors").route(web::get().to(get_ancestors)))
+        .service(web::resource("/container/{signature}/descendants").route(web::get().to(get_descendants)))
+        .service(web::resource("/container/{signature}/history").route(web::get().to(get_history)))
+        .service(web::resource("/container/{signature}/wave").route(web::get().to(get_wave)))
+        .service(web::resource("/container/{signature}/meta")
+            .route(web::get().to(get_container_meta))
+            .route(web::patch().to(patch_container_meta)))
         .service(web::resource("/containers").route(web::get().to(get_containers)))
         
+        // Cross-sensory binding
+        .service(web::resource("/bind").route(web::post().to(bind_containers)))
+        .service(web::resource("/container/{signature}/unpack").route(web::get().to(unpack_compound)))
+        .service(web::resource("/visual/similar/{signature}").route(web::get().to(similar_images)))
+        
+        // Retention endpoints
+        .service(web::resource("/retention").route(web::get().to(get_retention)))
+        .service(web::resource("/retention/policy").route(web::put().to(set_retention_policy)))
+        
         // Memory endpoints
         .service(web::resource("/mem8/context/latest").route(web::get().to(get_latest_language_memory)))
         .service(web::resource("/mem8/stats").route(web::get().to(get_nexus_stats)))
+        .service(web::resource("/mem8/memories").route(web::get().to(get_memories)))
+        .service(web::resource("/mem8/memory/{id}").route(web::get().to(get_memory)))
+        .service(web::resource("/mem8/memory/{id}/containers").route(web::get().to(get_memory_containers)))
         
         // SSE events
         .service(web::resource("/events").route(web::get().to(events_sse)))
         
         // Auctioneer WebSocket
         .service(web::resource("/auctioneer/live").route(web::get().to(auctioneer_ws)))
+        .service(web::resource("/battle/events").route(web::post().to(post_battle_event)))
+        .service(web::resource("/auctioneer/style")
+            .route(web::get().to(get_auctioneer_style))
+            .route(web::put().to(set_auctioneer_style)))
         
         // Serve the auctioneer HTML page
         .service(actix_files::Files::new("/static", "./static").index_file("auctioneer.html"));
 }
 
 /// GET /mem8/context/latest
-/// Returns the most recently stored language memory from Mem8
+/// Returns the most recently stored language memory, as the nexus handed it
+/// to MEM8, with the containers that reference it
 pub async fn get_latest_language_memory(
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let nexus_lock = nexus.lock().unwrap();
+    let Some((id, memory)) = nexus_lock.latest_language_memory() else {
+        return Err(ApiError::not_found("no_memories", "No context stored yet! Upload something first."));
+    };
+    
+    Ok(HttpResponse::Ok().json(LatestMemory {
+        id,
+        content: memory.content.clone(),
+        timestamp: humantime::format_rfc3339_millis(memory.stored_at).to_string(),
+        containers: memory_containers(&nexus_lock, id),
+    }))
+}
+
+/// GET /mem8/memories - Memories stored in a time window, oldest first
+pub async fn get_memories(
+    query: web::Query<MemoriesQuery>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let invalid_time = |msg| ApiError::bad_request("invalid_query", msg);
+    let since = match query.since.as_deref() {
+        Some(raw) => parse_time(raw).map_err(invalid_time)?,
+        None => SystemTime::now() - Duration::from_secs(3600),
+    };
+    let until = query.until.as_deref().map(parse_time).transpose().map_err(invalid_time)?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    
+    let nexus_lock = nexus.lock().unwrap();
+    let memories: Vec<MemorySummary> = nexus_lock.memories_between(since, until)
+        .take(limit)
+        .map(|(id, memory)| MemorySummary {
+            id,
+            content: memory.content.clone(),
+            timestamp: humantime::format_rfc3339_millis(memory.stored_at).to_string(),
+            containers: nexus_lock.containers_for_memory(id).iter().map(hex::encode).collect(),
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(memories))
+}
+
+/// GET /mem8/memory/{id} - One memory: content, importance, wave pattern and timestamp.
+/// The wave pattern comes from MEM8 by ID; the rest from the nexus's record.
+pub async fn get_memory(
+    path: web::Path<u64>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
     mem8: web::Data<Arc<Mutex<mem8::Mem8>>>,
-) -> Result<HttpResponse, Error> {
-    let mem8_lock = mem8.lock().unwrap();
-    let stats = mem8_lock.get_stats();
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let nexus_lock = nexus.lock().unwrap();
+    let containers = memory_containers(&nexus_lock, id);
+    let stored = nexus_lock.memory(id);
     
-    if stats.total_memories == 0 {
-        return Ok(HttpResponse::Ok().body("No context stored yet! Upload something first."));
+    let wave = mem8.lock().unwrap().get_wave_pattern(id).ok()
+        .map(|wave| WaveComponent { amplitude: wave.amplitude, frequency: wave.frequency, phase: wave.phase });
+    if wave.is_none() && stored.is_none() {
+        return Err(ApiError::not_found("memory_not_found", format!("Memory not found: {}", id)));
     }
     
-    // Get recent memories
-    let recent = mem8_lock.get_recent_memories(
-        std::time::SystemTime::now() - std::time::Duration::from_secs(3600)
-    ).unwrap_or_default();
+    Ok(HttpResponse::Ok().json(MemoryDetail {
+        id,
+        content: stored.map(|memory| memory.content.clone()),
+        importance: stored.and_then(|memory| memory.importance),
+        wave,
+        timestamp: stored.map(|memory| humantime::format_rfc3339_millis(memory.stored_at).to_string()),
+        containers,
+    }))
+}
+
+/// GET /mem8/memory/{id}/containers - Containers referencing a memory, oldest first
+pub async fn get_memory_containers(
+    path: web::Path<u64>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+    mem8: web::Data<Arc<Mutex<mem8::Mem8>>>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let nexus_lock = nexus.lock().unwrap();
+    let containers = memory_containers(&nexus_lock, id);
+    if containers.is_empty() && mem8.lock().unwrap().get_wave_pattern(id).is_err() {
+        return Err(ApiError::not_found("memory_not_found", format!("Memory not found: {}", id)));
+    }
+    Ok(HttpResponse::Ok().json(containers))
+}
+
+fn memory_containers(nexus: &M8Nexus, id: u64) -> Vec<ContainerSummary> {
+    let mut containers: Vec<&M8Container> = nexus.containers_for_memory(id).iter()
+        .filter_map(|sig| nexus.retrieve(sig))
+        .collect();
+    containers.sort_by_key(|c| (c.header.timestamp, c.wave_signature));
+    containers.into_iter().map(ContainerSummary::of).collect()
+}
+
+/// POST /upload/marqant - Upload and process Marqant files.
+/// Every multipart field is its own file; see `ingest_files` for the response shape.
+pub async fn upload_marqant(
+    req: HttpRequest,
+    payload: Multipart,
+    params: web::Query<UploadParams>,
+    ingestor: web::Data<SharedIngestor>,
+    jobs: web::Data<SharedJobQueue>,
+    idempotency: web::Data<SharedIdempotencyCache>,
+) -> Result<HttpResponse, ApiError> {
+    let files = read_multipart_files(payload, "upload.mq").await?;
+    if files.is_empty() {
+        return Err(ApiError::bad_request("no_files", "No files in upload"));
+    }
+    let parts: Vec<&[u8]> = files.iter().flat_map(|(name, bytes)| [name.as_bytes(), bytes]).collect();
+    let idempotency_key = IdempotencyCache::key_for(&req, &parts);
+    if let Some(replay) = replay_upload(&idempotency, &jobs, idempotency_key.as_ref())? {
+        return Ok(replay);
+    }
     
-    if recent.is_empty() {
-        return Ok(HttpResponse::Ok().body("No recent memories found."));
+    let params = params.into_inner();
+    let run_async = params.run_async;
+    let source = |name, bytes| IngestSource::Marqant { name, bytes };
+    let ingestor = ingestor.get_ref().clone();
+    let work = move |job| ingest_files(files, source, params, ingestor, job);
+    run_upload(run_async, work, &jobs, &idempotency, idempotency_key).await
+}
+
+/// POST /upload/text - Upload plain text
+pub async fn upload_text(
+    req: HttpRequest,
+    body: String,
+    params: web::Query<UploadParams>,
+    ingestor: web::Data<SharedIngestor>,
+    jobs: web::Data<SharedJobQueue>,
+    idempotency: web::Data<SharedIdempotencyCache>,
+) -> Result<HttpResponse, ApiError> {
+    let idempotency_key = IdempotencyCache::key_for(&req, &[body.as_bytes()]);
+    if let Some(replay) = replay_upload(&idempotency, &jobs, idempotency_key.as_ref())? {
+        return Ok(replay);
     }
     
-    // Get the most recent memory ID
-    let latest_id = recent.last().map(|(id, _, _)| *id).unwrap_or(0);
+    let run_async = params.run_async;
+    let request = IngestRequest::new(IngestSource::Text(body), params.into_inner());
+    let ingestor = ingestor.get_ref().clone();
+    let work = move |_| ingest_one(request, ingestor);
+    run_upload(run_async, work, &jobs, &idempotency, idempotency_key).await
+}
+
+/// POST /upload/batch - Ingest newline-delimited JSON records (`BatchRecord`).
+/// Streams back one NDJSON `BatchResult` per record as it is stored; a bad
+/// record gets a failed result and the batch carries on.
+pub async fn upload_batch(
+    payload: Payload,
+    params: web::Query<UploadParams>,
+    ingestor: web::Data<SharedIngestor>,
+) -> HttpResponse {
+    let batch = BatchIngest {
+        payload,
+        buffer: Vec::new(),
+        line: 0,
+        records: 0,
+        failed: 0,
+        skipping: false,
+        finished: false,
+        params: params.into_inner(),
+        ingestor: ingestor.get_ref().clone(),
+    };
+    
+    let results = futures::stream::unfold(batch, |mut batch| async move {
+        let result = batch.next_result().await?;
+        let mut line = serde_json::to_vec(&result).unwrap_or_default();
+        line.push(b'\n');
+        Some((Ok::<_, Error>(Bytes::from(line)), batch))
+    });
     
-    Ok(HttpResponse::Ok().body(format!(
-        "Latest memory ID: {}\nTotal memories: {}\nRecent memories (last hour): {}",
-        latest_id, stats.total_memories, recent.len()
-    )))
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(results)
 }
 
-/// POST /upload/marqant - Upload and process Marqant files
-pub async fn upload_marqant(
-    mut payload: Multipart,
-    event_queue: web::Data<EventQueue>,
-    mem8: web::Data<Arc<Mutex<mem8::Mem8>>>,
-    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
-    auctioneer: web::Data<Arc<Auctioneer>>,
-) -> Result<HttpResponse, Error> {
-    let mut file_bytes: Vec<u8> = Vec::new();
-    let mut filename = String::from("upload.mq");
+/// Longest batch record: room for a base64'd `MAX_PAYLOAD_SIZE` file and its JSON
+const MAX_BATCH_RECORD_BYTES: usize = MAX_PAYLOAD_SIZE / 3 * 4 + 64 * 1024;
+
+/// State of a streaming batch upload
+struct BatchIngest {
+    payload: Payload,
+    buffer: Vec<u8>,
+    line: usize,
+    records: usize, // Non-blank lines handled so far
+    failed: usize,
+    skipping: bool, // Dropping the rest of an over-long record
+    finished: bool,
+    params: UploadParams,
+    ingestor: SharedIngestor,
+}
+
+impl BatchIngest {
+    /// Ingest the next non-empty line, reading more of the body as needed
+    async fn next_result(&mut self) -> Option<BatchResult> {
+        loop {
+            let newline = self.buffer.iter().position(|&b| b == b'\n');
+            if self.skipping {
+                match newline {
+                    Some(end) => {
+                        self.buffer.drain(..=end);
+                        self.skipping = false;
+                        continue;
+                    }
+                    None => self.buffer.clear(),
+                }
+            }
+            
+            let record = match newline {
+                Some(end) => Some(self.buffer.drain(..=end).collect::<Vec<u8>>()),
+                None if self.finished && !self.buffer.is_empty() => Some(std::mem::take(&mut self.buffer)),
+                None if self.finished => {
+                    self.announce();
+                    return None;
+                }
+                None => None,
+            };
+            if let Some(record) = record {
+                self.line += 1;
+                if record.trim_ascii().is_empty() {
+                    continue;
+                }
+                self.records += 1;
+                if record.len() > MAX_BATCH_RECORD_BYTES {
+                    return Some(self.too_long());
+                }
+                let result = match self.ingest(&record).await {
+                    Ok(result) => result,
+                    Err(msg) => {
+                        self.failed += 1;
+                        failed_upload(&format!("line {}", self.line), &msg)
+                    }
+                };
+                return Some(BatchResult { line: self.line, result });
+            }
+            if self.buffer.len() > MAX_BATCH_RECORD_BYTES {
+                // No newline in sight; fail this record without buffering the rest of it
+                self.line += 1;
+                self.records += 1;
+                self.buffer.clear();
+                self.skipping = true;
+                return Some(self.too_long());
+            }
+            
+            match self.payload.next().await {
+                Some(Ok(chunk)) => self.buffer.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    // The body is broken; report it and stop reading
+                    self.finished = true;
+                    self.buffer.clear();
+                    self.failed += 1;
+                    return Some(BatchResult {
+                        line: self.line + 1,
+                        result: failed_upload("request body", &e.to_string()),
+                    });
+                }
+                None => self.finished = true,
+            }
+        }
+    }
+    
+    async fn ingest(&self, line: &[u8]) -> Result<UploadResponse, String> {
+        let record: BatchRecord = serde_json::from_slice(line).map_err(|e| format!("Invalid record: {}", e))?;
+        
+        let source = match (record.text, record.data) {
+            (Some(text), None) => IngestSource::Text(text),
+            (None, Some(data)) => {
+                let bytes = general_purpose::STANDARD.decode(data.trim())
+                    .map_err(|e| format!("Invalid base64 data: {}", e))?;
+                let name = record.filename.unwrap_or_else(|| "record.bin".to_string());
+                IngestSource::File { name, bytes }
+            }
+            _ => return Err("A record needs exactly one of `text` or `data`".to_string()),
+        };
+        
+        let mut request = IngestRequest::new(source, UploadParams {
+            parent: record.parent.or_else(|| self.params.parent.clone()),
+            ttl: record.ttl.or(self.params.ttl),
+            bind: false,
+            ..self.params.clone()
+        });
+        request.options.importance = record.importance;
+        request.options.metadata = record.metadata;
+        if !record.tags.is_empty() {
+            request.options.metadata.insert("tags".to_string(), record.tags.join(","));
+        }
+        
+        self.ingestor.ingest(request).await.map_err(|e| e.to_string())
+    }
+    
+    /// Fail the current line for being longer than `MAX_BATCH_RECORD_BYTES`
+    fn too_long(&mut self) -> BatchResult {
+        self.failed += 1;
+        let msg = format!("Record is longer than {} bytes", MAX_BATCH_RECORD_BYTES);
+        BatchResult { line: self.line, result: failed_upload(&format!("line {}", self.line), &msg) }
+    }
+    
+    /// Summarise the finished batch on the event stream
+    fn announce(&self) {
+        self.ingestor.events.publish(NexusEvent::BatchCompleted { records: self.records, failed: self.failed });
+    }
+}
+
+/// POST /upload/wave - Upload explicit wave parameters, either as a binary
+/// M8WV payload or as JSON `{"waves": [{"amplitude", "frequency", "phase"}], "importance"}`
+pub async fn upload_wave(
+    req: HttpRequest,
+    body: Bytes,
+    params: web::Query<UploadParams>,
+    ingestor: web::Data<SharedIngestor>,
+    jobs: web::Data<SharedJobQueue>,
+    idempotency: web::Data<SharedIdempotencyCache>,
+) -> Result<HttpResponse, ApiError> {
+    let idempotency_key = IdempotencyCache::key_for(&req, &[&body]);
+    if let Some(replay) = replay_upload(&idempotency, &jobs, idempotency_key.as_ref())? {
+        return Ok(replay);
+    }
+    
+    let upload = if body.starts_with(WAVE_MAGIC) {
+        decode_wave_payload(&body)
+            .map(|waves| WaveUpload { waves, importance: default_importance() })
+            .map_err(|e| e.to_string())
+    } else {
+        serde_json::from_slice::<WaveUpload>(&body).map_err(|e| e.to_string())
+    };
+    let upload = match upload {
+        Ok(upload) => upload,
+        Err(msg) => return Err(ApiError::bad_request("invalid_wave_payload", format!("Invalid wave payload: {}", msg))),
+    };
     
-    // Process multipart upload
+    let run_async = params.run_async;
+    let mut request = IngestRequest::new(IngestSource::Waves(upload.waves), params.into_inner());
+    request.options.importance = upload.importance;
+    let ingestor = ingestor.get_ref().clone();
+    let work = move |_| ingest_one(request, ingestor);
+    run_upload(run_async, work, &jobs, &idempotency, idempotency_key).await
+}
+
+/// Generic upload handler (auto-detects format).
+/// Every multipart field is its own file; see `ingest_files` for the response shape.
+pub async fn upload_handler(
+    req: HttpRequest,
+    payload: Multipart,
+    params: web::Query<UploadParams>,
+    ingestor: web::Data<SharedIngestor>,
+    jobs: web::Data<SharedJobQueue>,
+    idempotency: web::Data<SharedIdempotencyCache>,
+) -> Result<HttpResponse, ApiError> {
+    let files = read_multipart_files(payload, "upload.bin").await?;
+    if files.is_empty() {
+        return Err(ApiError::bad_request("no_files", "No files in upload"));
+    }
+    let parts: Vec<&[u8]> = files.iter().flat_map(|(name, bytes)| [name.as_bytes(), bytes]).collect();
+    let idempotency_key = IdempotencyCache::key_for(&req, &parts);
+    if let Some(replay) = replay_upload(&idempotency, &jobs, idempotency_key.as_ref())? {
+        return Ok(replay);
+    }
+    
+    let params = params.into_inner();
+    let run_async = params.run_async;
+    let source = |name, bytes| IngestSource::File { name, bytes };
+    let ingestor = ingestor.get_ref().clone();
+    let work = move |job| ingest_files(files, source, params, ingestor, job);
+    run_upload(run_async, work, &jobs, &idempotency, idempotency_key).await
+}
+
+/// Per-file result for a file that couldn't be stored
+fn failed_upload(file_name: &str, message: &str) -> UploadResponse {
+    UploadResponse {
+        success: false,
+        wave_signature: String::new(),
+        content_type: "error".to_string(),
+        memory_ids: vec![],
+        compression_ratio: None,
+        message: format!("{}: {}", file_name, message),
+        duplicate: false,
+    }
+}
+
+/// Read every multipart field as its own file: (filename, bytes)
+async fn read_multipart_files(mut payload: Multipart, default_name: &str) -> Result<Vec<(String, Vec<u8>)>, ApiError> {
+    let mut files = Vec::new();
     while let Some(field_result) = payload.next().await {
         let mut field = field_result?;
-        let content_disp = field.content_disposition();
-        if let Some(name) = content_disp.get_filename() {
-            filename = name.to_string();
-        }
-            
+        let filename = field.content_disposition().get_filename()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| default_name.to_string());
+        
+        let mut file_bytes = Vec::new();
         while let Some(chunk) = field.next().await {
-            let data = chunk?;
-            file_bytes.extend_from_slice(&data);
+            file_bytes.extend_from_slice(&chunk?);
         }
+        files.push((filename, file_bytes));
+    }
+    Ok(files)
+}
+
+/// Run every file of a multipart upload through the pipeline. A lone file
+/// gets the plain upload response, errors included; several files (or
+/// `?bind=true`) get a `MultiUploadResponse` with one result per file, and
+/// with `?bind=true` a compound container binding them all.
+async fn ingest_files(
+    mut files: Vec<(String, Vec<u8>)>,
+    source: fn(String, Vec<u8>) -> IngestSource,
+    params: UploadParams,
+    ingestor: SharedIngestor,
+    job: Option<JobHandle>,
+) -> Result<serde_json::Value, IngestError> {
+    if files.len() == 1 && !params.bind {
+        let (name, bytes) = files.remove(0);
+        return ingest_one(IngestRequest::new(source(name, bytes), params), ingestor).await;
     }
     
-    // Parse Marqant
-    let marqant = match Marqant::from_bytes(&file_bytes) {
-        Ok(mq) => mq,
-        Err(e) => {
-            // Try to create from raw markdown
-            let markdown = String::from_utf8_lossy(&file_bytes);
-            match Marqant::from_markdown(&markdown) {
-                Ok(mq) => mq,
-                Err(_) => return Ok(HttpResponse::BadRequest().json(UploadResponse {
-                    success: false,
-                    wave_signature: String::new(),
-                    content_type: "error".to_string(),
-                    memory_ids: vec![],
-                    compression_ratio: None,
-                    message: format!("Failed to parse Marqant: {}", e),
-                })),
-            }
+    let total = files.len();
+    let mut results = Vec::with_capacity(total);
+    for (name, bytes) in files {
+        let label = name.clone();
+        let result = ingestor.ingest(IngestRequest::new(source(name, bytes), params.clone())).await;
+        results.push(result.unwrap_or_else(|e| failed_upload(&label, &e.to_string())));
+        if let Some(job) = &job {
+            job.progress(results.len(), total);
+        }
+    }
+    
+    let compound = if params.bind {
+        bind_uploaded(&results, &params, &ingestor).await?
+    } else {
+        None
+    };
+    
+    let stored = results.iter().filter(|file| file.success).count();
+    let mut message = format!("{} of {} files stored", stored, results.len());
+    if params.bind && compound.is_none() {
+        message.push_str("; not bound, binding needs at least two memories");
+    }
+    
+    let response = MultiUploadResponse {
+        success: stored == results.len(),
+        message,
+        files: results,
+        compound,
+    };
+    serde_json::to_value(response).map_err(|e| IngestError::Internal(e.to_string()))
+}
+
+/// Run a single item through the pipeline
+async fn ingest_one(request: IngestRequest, ingestor: SharedIngestor) -> Result<serde_json::Value, IngestError> {
+    let response = ingestor.ingest(request).await?;
+    serde_json::to_value(response).map_err(|e| IngestError::Internal(e.to_string()))
+}
+
+/// Run an upload now, or with `?async=true` queue it as a background job and
+/// answer 202 Accepted with the job; its result shows up at GET /jobs/{id}
+async fn run_upload<F, Fut>(
+    run_async: bool,
+    work: F,
+    jobs: &SharedJobQueue,
+    idempotency: &IdempotencyCache,
+    key: Option<IdempotencyKey>,
+) -> Result<HttpResponse, ApiError>
+where
+    F: FnOnce(Option<JobHandle>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<serde_json::Value, IngestError>> + Send + 'static,
+{
+    if !run_async {
+        let response = work(None).await?;
+        return Ok(respond_upload(idempotency, key, response));
+    }
+    
+    let job = jobs.submit("upload", |handle| work(Some(handle)));
+    // A retry with the same key gets this job back instead of queueing another
+    if let Some(key) = key {
+        idempotency.remember(key, Remembered::Job(job.id));
+    }
+    Ok(accepted_job(&job, false))
+}
+
+/// 202 Accepted for a background job, pointing at where to poll it
+fn accepted_job(job: &Job, replayed: bool) -> HttpResponse {
+    let mut response = HttpResponse::Accepted();
+    response.insert_header((header::LOCATION, format!("/jobs/{}", job.id)));
+    if replayed {
+        response.insert_header((REPLAYED_HEADER, "true"));
+    }
+    response.json(job)
+}
+
+/// Bind the memories of every stored file into one compound container.
+/// Needs at least two distinct memories, like POST /bind.
+async fn bind_uploaded(
+    files: &[UploadResponse],
+    params: &UploadParams,
+    ingestor: &SharedIngestor,
+) -> Result<Option<UploadResponse>, IngestError> {
+    let stored: Vec<&UploadResponse> = files.iter().filter(|file| file.success).collect();
+    let mut seen = std::collections::HashSet::new();
+    let memory_ids: Vec<u64> = stored.iter()
+        .flat_map(|file| file.memory_ids.iter().copied())
+        .filter(|id| seen.insert(*id))
+        .collect();
+    if memory_ids.len() < 2 {
+        return Ok(None);
+    }
+    
+    let emotion = EmotionInput {
+        valence: params.valence.unwrap_or_default(),
+        arousal: params.arousal.unwrap_or_default(),
+        dominance: params.dominance.unwrap_or_default(),
+    };
+    let source = IngestSource::Compound {
+        memory_ids,
+        emotion: emotion.to_context(),
+        bound_signatures: stored.iter().filter_map(|file| parse_signature(&file.wave_signature)).collect(),
+    };
+    // The files already carry the lineage; the compound is new
+    let params = UploadParams { parent: None, ..params.clone() };
+    ingestor.ingest(IngestRequest::new(source, params)).await.map(Some)
+}
+
+/// Replay a remembered upload response for a retried request. A background
+/// upload replays as its job's current state.
+fn replay_upload(
+    idempotency: &IdempotencyCache,
+    jobs: &SharedJobQueue,
+    key: Option<&IdempotencyKey>,
+) -> Result<Option<HttpResponse>, ApiError> {
+    let Some(key) = key else {
+        return Ok(None);
+    };
+    let response = match idempotency.replay(key)? {
+        None => return Ok(None),
+        Some(Remembered::Response(body)) => HttpResponse::Ok()
+            .insert_header((REPLAYED_HEADER, "true"))
+            .json(body),
+        Some(Remembered::Job(id)) => {
+            let job = jobs.get(id)
+                .ok_or_else(|| ApiError::not_found("job_not_found", format!("Job {} for this Idempotency-Key has expired", id)))?;
+            accepted_job(&job, true)
+        }
+    };
+    Ok(Some(response))
+}
+
+/// Reply to an upload, remembering the response under its idempotency key
+fn respond_upload<T: Serialize>(
+    idempotency: &IdempotencyCache,
+    key: Option<IdempotencyKey>,
+    response: T,
+) -> HttpResponse {
+    if let Some(key) = key {
+        if let Ok(body) = serde_json::to_value(&response) {
+            idempotency.remember(key, Remembered::Response(body));
         }
+    }
+    HttpResponse::Ok().json(response)
+}
+
+/// GET /jobs/{id} - Status of a background upload, with its result once done
+pub async fn get_job(
+    path: web::Path<u64>,
+    jobs: web::Data<SharedJobQueue>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    match jobs.get(id) {
+        Some(job) => Ok(HttpResponse::Ok().json(job)),
+        None => Err(ApiError::not_found("job_not_found", format!("Job not found: {}", id))),
+    }
+}
+
+/// GET /container/{signature} - Retrieve container by wave signature.
+/// Serves the content by default; `?format=` or the Accept header pick the
+/// original `.m8` or `.mq` file, markdown, or JSON with the full header.
+pub async fn get_container(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<ContainerQuery>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let signature_hex = path.into_inner();
+    let signature_bytes = match parse_signature(&signature_hex) {
+        Some(sig) => sig,
+        None => return Err(ApiError::invalid_signature()),
+    };
+    let format = ContainerFormat::negotiate(&req, query.format.as_deref())?;
+    
+    let nexus_lock = nexus.lock().unwrap();
+    let Some(container) = nexus_lock.retrieve(&signature_bytes) else {
+        return Err(ApiError::container_not_found());
+    };
+    let attachment = |ext: &str| (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", signature_hex, ext));
+    
+    match (format, &container.header.content_type) {
+        // The whole container, byte-for-byte re-uploadable to /upload
+        (ContainerFormat::M8, _) => Ok(HttpResponse::Ok()
+            .content_type(crate::mime::M8_MIME)
+            .insert_header(attachment("m8"))
+            .body(container.to_bytes().map_err(ApiError::internal)?)),
+        (ContainerFormat::Marqant, M8ContentType::Marqant) => Ok(HttpResponse::Ok()
+            .content_type(crate::mime::MARQANT_MIME)
+            .insert_header(attachment("mq"))
+            .body(container.payload().map_err(ApiError::internal)?.into_owned())),
+        (ContainerFormat::Markdown, M8ContentType::Marqant | M8ContentType::Language) => Ok(HttpResponse::Ok()
+            .content_type("text/markdown; charset=utf-8")
+            .body(container.extract_content().map_err(ApiError::internal)?)),
+        (ContainerFormat::Marqant | ContainerFormat::Markdown, other) => Err(ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            "not_acceptable",
+            format!("A {:?} container has no {:?} representation", other, format),
+        )),
+        (ContainerFormat::Json, content_type) => {
+            let data = match content_type {
+                M8ContentType::Visual | M8ContentType::Audio | M8ContentType::Blob => {
+                    Some(general_purpose::STANDARD.encode(container.payload().map_err(ApiError::internal)?))
+                }
+                _ => None,
+            };
+            Ok(HttpResponse::Ok().json(ContainerJson {
+                header: ContainerHeader::of(container),
+                content: container.extract_content().map_err(ApiError::internal)?,
+                data,
+            }))
+        }
+        // Blobs go back exactly as they came in
+        (ContainerFormat::Content, M8ContentType::Blob) => {
+            let mime = container.header.metadata.get("mime").map(String::as_str).unwrap_or(crate::mime::OCTET_STREAM);
+            Ok(HttpResponse::Ok()
+                .content_type(mime)
+                .body(container.payload().map_err(ApiError::internal)?.into_owned()))
+        }
+        (ContainerFormat::Content, _) => Ok(HttpResponse::Ok()
+            .content_type("text/plain")
+            .body(container.extract_content().map_err(ApiError::internal)?)),
+    }
+}
+
+/// GET /container/{signature}/wave - Wave payload of a WavePattern or Compound
+/// container, as JSON or (`?format=raw` / `Accept: application/octet-stream`) binary
+pub async fn get_wave(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<WaveQuery>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(signature) = parse_signature(&path.into_inner()) else {
+        return Err(ApiError::invalid_signature());
+    };
+    
+    let nexus_lock = nexus.lock().unwrap();
+    let Some(container) = nexus_lock.retrieve(&signature) else {
+        return Err(ApiError::container_not_found());
     };
+    if !matches!(container.header.content_type, M8ContentType::WavePattern | M8ContentType::Compound) {
+        return Err(ApiError::bad_request("not_a_wave_pattern", "Container does not hold a wave pattern"));
+    }
     
-    let compression_ratio = marqant.compression_ratio();
+    let wants_raw = query.format.as_deref() == Some("raw")
+        || req.headers().get(actix_web::http::header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("application/octet-stream"));
+    if wants_raw {
+        // Older compounds hold bare entries; always serve the documented layout
+        let waves = container.payload().and_then(|data| decode_wave_payload(&data))
+            .map_err(ApiError::internal)?;
+        return Ok(HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .body(encode_wave_payload(&waves)));
+    }
     
-    // Create M8 container
-    let container = M8Container::from_marqant(&marqant, mem8.get_ref().clone()).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-    let wave_signature = container.wave_signature;
-    let memory_ids = container.header.memory_ids.clone();
+    let content = container.extract_content().map_err(ApiError::internal)?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(content))
+}
+
+/// GET /container/{signature}/meta - The full header of a container, with sizes
+pub async fn get_container_meta(
+    path: web::Path<String>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(signature) = parse_signature(&path.into_inner()) else {
+        return Err(ApiError::invalid_signature());
+    };
     
-    // Store in nexus
-    let stored_container = container.clone();
-    let mut nexus_lock = nexus.lock().unwrap();
-    nexus_lock.store(container);
-    drop(nexus_lock);
+    let nexus_lock = nexus.lock().unwrap();
+    let Some(container) = nexus_lock.retrieve(&signature) else {
+        return Err(ApiError::container_not_found());
+    };
+    let meta = ContainerMeta::of(container).map_err(ApiError::internal)?;
+    Ok(HttpResponse::Ok().json(meta))
+}
+
+/// PATCH /container/{signature}/meta - Edit user metadata, merge-patch style:
+/// `{"key": "value"}` sets a key and `{"key": null}` removes it. Keys the nexus
+/// writes itself are read-only. The content, and so the signature, never changes.
+pub async fn patch_container_meta(
+    path: web::Path<String>,
+    body: web::Json<HashMap<String, Option<String>>>,
+    events: web::Data<SharedEventHub>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let signature_hex = path.into_inner();
+    let Some(signature) = parse_signature(&signature_hex) else {
+        return Err(ApiError::invalid_signature());
+    };
+    let changes = body.into_inner();
+    if let Some(key) = changes.keys().find(|key| key.trim().is_empty()) {
+        return Err(ApiError::bad_request("invalid_metadata_key", format!("Invalid metadata key: {:?}", key)));
+    }
+    if let Some(key) = changes.keys().find(|key| SYSTEM_METADATA_KEYS.contains(&key.as_str())) {
+        return Err(ApiError::bad_request(
+            "protected_metadata_key",
+            format!("Metadata key '{}' is managed by the nexus and can't be edited", key),
+        ));
+    }
     
-    // Notify auctioneer about the contribution
-    let contributor_id = "quantum_uploader"; // In a real system, this would be from auth
-    auctioneer.process_contribution(
-        contributor_id,
-        &stored_container,
-        mem8.get_ref().clone()
-    ).await;
+    let mut keys: Vec<String> = changes.keys().cloned().collect();
+    keys.sort();
+    let meta = {
+        let mut nexus_lock = nexus.lock().unwrap();
+        let Some(container) = nexus_lock.update_metadata(&signature, changes) else {
+            return Err(ApiError::container_not_found());
+        };
+        ContainerMeta::of(container).map_err(ApiError::internal)?
+    };
     
     // Notify via SSE
-    let msg = format!(
-        "Marqant uploaded: {} (compression: {:.2}x)",
-        filename, compression_ratio
-    );
-    let mut queue = event_queue.lock().unwrap();
-    queue.push_back(msg);
-    
-    Ok(HttpResponse::Ok().json(UploadResponse {
-        success: true,
-        wave_signature: hex::encode(wave_signature),
-        content_type: "marqant".to_string(),
-        memory_ids,
-        compression_ratio: Some(compression_ratio),
-        message: format!("Marqant uploaded successfully! Compression ratio: {:.2}x", compression_ratio),
-    }))
+    events.publish(NexusEvent::MetadataUpdated { signature: signature_hex, keys });
+    
+    Ok(HttpResponse::Ok().json(meta))
 }
 
-/// POST /upload/text - Upload plain text
-pub async fn upload_text(
-    body: String,
-    event_queue: web::Data<EventQueue>,
-    mem8: web::Data<Arc<Mutex<mem8::Mem8>>>,
+/// DELETE /container/{signature} - Remove a container and release unused memories
+pub async fn delete_container(
+    path: web::Path<String>,
+    events: web::Data<SharedEventHub>,
     nexus: web::Data<Arc<Mutex<M8Nexus>>>,
-) -> Result<HttpResponse, Error> {
-    // Create M8 container from text
-    let container = M8Container::from_text(&body, 5, mem8.get_ref().clone()).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-    let wave_signature = container.wave_signature;
-    let memory_ids = container.header.memory_ids.clone();
+) -> Result<HttpResponse, ApiError> {
+    let signature = match parse_signature(&path.into_inner()) {
+        Some(sig) => sig,
+        None => return Err(ApiError::invalid_signature()),
+    };
     
-    // Store in nexus
-    let mut nexus_lock = nexus.lock().unwrap();
-    nexus_lock.store(container);
+    let removal = match nexus.lock().unwrap().remove(&signature) {
+        Some(removal) => removal,
+        None => return Err(ApiError::container_not_found()),
+    };
+    
+    #[derive(Serialize)]
+    struct DeleteResponse {
+        success: bool,
+        wave_signature: String,
+        content_type: String,
+        unreferenced_memory_ids: Vec<u64>,
+        flagged_compounds: Vec<String>,
+    }
     
     // Notify via SSE
-    let msg = format!("Text uploaded: {} bytes", body.len());
-    let mut queue = event_queue.lock().unwrap();
-    queue.push_back(msg);
+    events.publish(NexusEvent::ContainerDeleted {
+        signature: hex::encode(signature),
+        content_type: removal.container.header.content_type.clone(),
+        unreferenced_memory_ids: removal.unreferenced_memory_ids.clone(),
+    });
     
-    Ok(HttpResponse::Ok().json(UploadResponse {
+    Ok(HttpResponse::Ok().json(DeleteResponse {
         success: true,
-        wave_signature: hex::encode(wave_signature),
-        content_type: "text".to_string(),
-        memory_ids,
-        compression_ratio: None,
-        message: "Text uploaded and stored in quantum memory!".to_string(),
+        wave_signature: hex::encode(signature),
+        content_type: format!("{:?}", removal.container.header.content_type),
+        unreferenced_memory_ids: removal.unreferenced_memory_ids,
+        flagged_compounds: removal.flagged_compounds.iter().map(hex::encode).collect(),
     }))
 }
 
-/// Generic upload handler (auto-detects format)
-pub async fn upload_handler(
-    mut payload: Multipart,
-    _event_queue: web::Data<EventQueue>,
-    mem8: web::Data<Arc<Mutex<mem8::Mem8>>>,
+/// GET /container/{signature}/ancestors - Parent chain, nearest first
+pub async fn get_ancestors(
+    path: web::Path<String>,
     nexus: web::Data<Arc<Mutex<M8Nexus>>>,
-) -> Result<HttpResponse, Error> {
-    let mut file_type = String::from("unknown");
-    let mut file_name = String::from("upload.bin");
-    let mut file_bytes: Vec<u8> = Vec::new();
+) -> Result<HttpResponse, ApiError> {
+    let signature = match parse_signature(&path.into_inner()) {
+        Some(sig) => sig,
+        None => return Err(ApiError::invalid_signature()),
+    };
     
-    while let Some(field_result) = payload.next().await {
-        let mut field = field_result?;
-        let content_disp = field.content_disposition();
-        let filename = content_disp.get_filename()
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| "upload.bin".to_string());
-        let ext = Path::new(&filename).extension().and_then(|e| e.to_str()).unwrap_or("");
-        file_type = ext.to_string();
-        file_name = filename;
-        
-        while let Some(chunk) = field.next().await {
-            let data = chunk?;
-            file_bytes.extend_from_slice(&data);
-        }
+    let nexus_lock = nexus.lock().unwrap();
+    if nexus_lock.retrieve(&signature).is_none() {
+        return Err(ApiError::container_not_found());
     }
+    let ancestors: Vec<String> = nexus_lock.ancestors(&signature).iter().map(hex::encode).collect();
     
-    // Route based on file type
-    match file_type.as_str() {
-        "mq" => {
-            // Parse as Marqant
-            let marqant = Marqant::from_bytes(&file_bytes).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-            let compression_ratio = marqant.compression_ratio();
-            let container = M8Container::from_marqant(&marqant, mem8.get_ref().clone()).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-            let wave_signature = container.wave_signature;
-            let memory_ids = container.header.memory_ids.clone();
-            
-            let mut nexus_lock = nexus.lock().unwrap();
-            nexus_lock.store(container);
-            
-            Ok(HttpResponse::Ok().json(UploadResponse {
-                success: true,
-                wave_signature: hex::encode(wave_signature),
-                content_type: "marqant".to_string(),
-                memory_ids,
-                compression_ratio: Some(compression_ratio),
-                message: format!("Marqant uploaded! Compression: {:.2}x", compression_ratio),
-            }))
-        }
-        "m8" => {
-            // Parse as M8 container
-            let container = M8Container::from_bytes(&file_bytes).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-            let wave_signature = container.wave_signature;
-            let memory_ids = container.header.memory_ids.clone();
-            let content_type = format!("{:?}", container.header.content_type);
-            
-            let mut nexus_lock = nexus.lock().unwrap();
-            nexus_lock.store(container);
-            
-            Ok(HttpResponse::Ok().json(UploadResponse {
-                success: true,
-                wave_signature: hex::encode(wave_signature),
-                content_type,
-                memory_ids,
-                compression_ratio: None,
-                message: "M8 container uploaded successfully!".to_string(),
-            }))
+    Ok(HttpResponse::Ok().json(ancestors))
+}
+
+/// GET /container/{signature}/descendants - Every later revision, nearest first
+pub async fn get_descendants(
+    path: web::Path<String>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let signature = match parse_signature(&path.into_inner()) {
+        Some(sig) => sig,
+        None => return Err(ApiError::invalid_signature()),
+    };
+    
+    let nexus_lock = nexus.lock().unwrap();
+    if nexus_lock.retrieve(&signature).is_none() {
+        return Err(ApiError::container_not_found());
+    }
+    let descendants: Vec<String> = nexus_lock.descendants(&signature).iter().map(hex::encode).collect();
+    
+    Ok(HttpResponse::Ok().json(descendants))
+}
+
+/// GET /container/{signature}/history - Version history as a DAG
+pub async fn get_history(
+    path: web::Path<String>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let signature = match parse_signature(&path.into_inner()) {
+        Some(sig) => sig,
+        None => return Err(ApiError::invalid_signature()),
+    };
+    
+    let nexus_lock = nexus.lock().unwrap();
+    if nexus_lock.retrieve(&signature).is_none() {
+        return Err(ApiError::container_not_found());
+    }
+    let history = nexus_lock.history(&signature);
+    
+    #[derive(Serialize)]
+    struct HistoryNode {
+        signature: String,
+        content_type: Option<String>,
+        timestamp: Option<String>,
+        parents: Vec<String>,
+    }
+    
+    #[derive(Serialize)]
+    struct HistoryEdge {
+        child: String,
+        parent: String,
+    }
+    
+    #[derive(Serialize)]
+    struct HistoryDag {
+        root: String,
+        nodes: Vec<HistoryNode>,
+        edges: Vec<HistoryEdge>,
+    }
+    
+    // Ancestors that have since left the nexus still appear, just without details
+    let nodes = history.nodes.iter()
+        .map(|sig| {
+            let container = nexus_lock.retrieve(sig);
+            HistoryNode {
+                signature: hex::encode(sig),
+                content_type: container.map(|c| format!("{:?}", c.header.content_type)),
+                timestamp: container.map(|c| humantime::format_rfc3339_millis(c.header.timestamp).to_string()),
+                parents: container
+                    .map(|c| c.header.parents.iter().map(hex::encode).collect())
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+    let edges = history.edges.iter()
+        .map(|(child, parent)| HistoryEdge {
+            child: hex::encode(child),
+            parent: hex::encode(parent),
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(HistoryDag {
+        root: hex::encode(signature),
+        nodes,
+        edges,
+    }))
+}
+
+/// GET /containers - One page of containers, newest first unless asked otherwise.
+/// Filters by content type, time window, metadata and tag; follow `next_cursor` for more.
+pub async fn get_containers(
+    query: web::Query<ContainersQuery>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+) -> Result<HttpResponse, ApiError> {
+    let query = query.to_query()?;
+    
+    let nexus_lock = nexus.lock().unwrap();
+    let page = nexus_lock.page(&query);
+    let containers = page.containers.into_iter().map(ContainerSummary::of).collect();
+    
+    Ok(HttpResponse::Ok().json(ContainerList {
+        containers,
+        next_cursor: page.next.map(|cursor| encode_cursor(query.sort, cursor)),
+    }))
+}
+
+/// POST /bind - Bind containers and/or memories into a Compound container
+pub async fn bind_containers(
+    req: HttpRequest,
+    body: web::Json<BindRequest>,
+    params: web::Query<BindParams>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+    ingestor: web::Data<SharedIngestor>,
+    jobs: web::Data<SharedJobQueue>,
+    idempotency: web::Data<SharedIdempotencyCache>,
+) -> Result<HttpResponse, ApiError> {
+    let bind = body.into_inner();
+    let fingerprint = serde_json::to_vec(&bind).map_err(ApiError::internal)?;
+    let idempotency_key = IdempotencyCache::key_for(&req, &[&fingerprint]);
+    if let Some(replay) = replay_upload(&idempotency, &jobs, idempotency_key.as_ref())? {
+        return Ok(replay);
+    }
+    
+    // Resolve every signature to its container's memories
+    let mut bound_signatures = Vec::new();
+    let mut memory_ids = Vec::new();
+    {
+        let nexus_lock = nexus.lock().unwrap();
+        for sig_hex in &bind.signatures {
+            let Some(sig) = parse_signature(sig_hex) else {
+                return Err(ApiError::bad_request("invalid_signature", format!("Invalid signature: {}", sig_hex)));
+            };
+            let Some(container) = nexus_lock.retrieve(&sig) else {
+                return Err(ApiError::not_found("container_not_found", format!("Container not found: {}", sig_hex)));
+            };
+            if !bound_signatures.contains(&sig) {
+                bound_signatures.push(sig);
+            }
+            memory_ids.extend(container.header.memory_ids.iter().copied());
         }
-        _ => {
-            // Treat as text
-            let text = String::from_utf8_lossy(&file_bytes);
-            let container = M8Container::from_text(&text, 5, mem8.get_ref().clone()).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-            let wave_signature = container.wave_signature;
-            let memory_ids = container.header.memory_ids.clone();
-            
-            let mut nexus_lock = nexus.lock().unwrap();
-            nexus_lock.store(container);
-            
-            Ok(HttpResponse::Ok().json(UploadResponse {
-                success: true,
-                wave_signature: hex::encode(wave_signature),
-                content_type: "text".to_string(),
-                memory_ids,
-                compression_ratio: None,
-                message: format!("File '{}' uploaded as text", file_name),
-            }))
+        
+        // Loose memory IDs must exist in either the nexus or MEM8
+        let mem8_lock = ingestor.mem8.lock().unwrap();
+        for &id in &bind.memory_ids {
+            if nexus_lock.containers_for_memory(id).is_empty() && mem8_lock.get_wave_pattern(id).is_err() {
+                return Err(ApiError::not_found("memory_not_found", format!("Memory not found: {}", id)));
+            }
         }
     }
+    memory_ids.extend(bind.memory_ids.iter().copied());
+    let mut seen = std::collections::HashSet::new();
+    memory_ids.retain(|id| seen.insert(*id));
+    
+    if memory_ids.len() < 2 {
+        return Err(ApiError::bad_request("not_enough_memories", "Binding needs at least two memories"));
+    }
+    
+    let source = IngestSource::Compound {
+        memory_ids,
+        emotion: bind.emotion.to_context(),
+        bound_signatures,
+    };
+    let params = UploadParams { ttl: params.ttl, run_async: params.run_async, ..UploadParams::default() };
+    let run_async = params.run_async;
+    let request = IngestRequest::new(source, params);
+    let ingestor = ingestor.get_ref().clone();
+    let work = move |_| ingest_one(request, ingestor);
+    run_upload(run_async, work, &jobs, &idempotency, idempotency_key).await
 }
 
-/// GET /container/{signature} - Retrieve container by wave signature
-pub async fn get_container(
+/// GET /container/{signature}/unpack - Member containers and wave patterns of a compound
+pub async fn unpack_compound(
     path: web::Path<String>,
     nexus: web::Data<Arc<Mutex<M8Nexus>>>,
-) -> Result<HttpResponse, Error> {
-    let signature_hex = path.into_inner();
-    let signature_bytes = match hex::decode(&signature_hex) {
-        Ok(bytes) if bytes.len() == 32 => {
-            let mut arr = [0u8; 32];
-            arr.copy_from_slice(&bytes);
-            arr
-        }
-        _ => return Ok(HttpResponse::BadRequest().body("Invalid signature format")),
+) -> Result<HttpResponse, ApiError> {
+    let signature = match parse_signature(&path.into_inner()) {
+        Some(sig) => sig,
+        None => return Err(ApiError::invalid_signature()),
     };
     
+    #[derive(Serialize)]
+    struct Member {
+        signature: String,
+        content_type: String,
+        memory_ids: Vec<u64>,
+    }
+    
+    #[derive(Serialize)]
+    struct Wave {
+        memory_id: u64,
+        amplitude: f32,
+        frequency: f32,
+        phase: f32,
+    }
+    
+    #[derive(Serialize)]
+    struct Unpacked {
+        signature: String,
+        emotional_context: [f32; 3],
+        memory_ids: Vec<u64>,
+        members: Vec<Member>,
+        unbound: Vec<String>,
+        waves: Vec<Wave>,
+    }
+    
     let nexus_lock = nexus.lock().unwrap();
-    if let Some(container) = nexus_lock.retrieve(&signature_bytes) {
-        let content = container.extract_content().map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-        Ok(HttpResponse::Ok()
-            .content_type("text/plain")
-            .body(content))
-    } else {
-        Ok(HttpResponse::NotFound().body("Container not found"))
+    let Some(compound) = nexus_lock.retrieve(&signature) else {
+        return Err(ApiError::container_not_found());
+    };
+    if !matches!(compound.header.content_type, M8ContentType::Compound) {
+        return Err(ApiError::bad_request("not_a_compound", "Container is not a compound"));
     }
+    
+    let members = nexus_lock.compound_members(&signature).iter()
+        .filter_map(|sig| nexus_lock.retrieve(sig))
+        .map(|member| Member {
+            signature: hex::encode(member.wave_signature),
+            content_type: format!("{:?}", member.header.content_type),
+            memory_ids: member.header.memory_ids.clone(),
+        })
+        .collect();
+    let unbound = compound.header.metadata.get("unbound")
+        .map(|list| list.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+    
+    // Waves are captured in the payload at bind time, aligned with memory_ids
+    let waves = compound.payload().and_then(|data| decode_wave_payload(&data))
+        .map_err(ApiError::internal)?
+        .into_iter()
+        .zip(&compound.header.memory_ids)
+        .map(|(wave, &id)| Wave {
+            memory_id: id,
+            amplitude: wave.amplitude,
+            frequency: wave.frequency,
+            phase: wave.phase,
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(Unpacked {
+        signature: hex::encode(signature),
+        emotional_context: emotional_bytes_to_vad(&compound.header.emotional_context),
+        memory_ids: compound.header.memory_ids.clone(),
+        members,
+        unbound,
+        waves,
+    }))
 }
 
-/// GET /containers - List all containers
-pub async fn get_containers(
+/// GET /visual/similar/{signature} - Images that look like the given one
+pub async fn similar_images(
+    path: web::Path<String>,
+    query: web::Query<SimilarityQuery>,
     nexus: web::Data<Arc<Mutex<M8Nexus>>>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, ApiError> {
+    let signature = match parse_signature(&path.into_inner()) {
+        Some(sig) => sig,
+        None => return Err(ApiError::invalid_signature()),
+    };
+    
     let nexus_lock = nexus.lock().unwrap();
-    let containers = nexus_lock.list();
+    let Some(container) = nexus_lock.retrieve(&signature) else {
+        return Err(ApiError::container_not_found());
+    };
+    let Some(phash) = container.header.metadata.get(PHASH_KEY)
+        .and_then(|hash| u64::from_str_radix(hash, 16).ok())
+    else {
+        return Err(ApiError::bad_request("not_an_image", "Container is not an image"));
+    };
+    
+    #[derive(Serialize)]
+    struct SimilarImage {
+        signature: String,
+        distance: u32,
+        width: Option<String>,
+        height: Option<String>,
+    }
+    
+    let similar: Vec<SimilarImage> = nexus_lock.similar_images(phash, query.max_distance.unwrap_or(10))
+        .into_iter()
+        .filter(|(sig, _)| *sig != signature)
+        .filter_map(|(sig, distance)| {
+            let image = nexus_lock.retrieve(&sig)?;
+            Some(SimilarImage {
+                signature: hex::encode(sig),
+                distance,
+                width: image.header.metadata.get("width").cloned(),
+                height: image.header.metadata.get("height").cloned(),
+            })
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(similar))
+}
+
+/// GET /retention - Current retention policy and upcoming expiries
+pub async fn get_retention(
+    query: web::Query<RetentionQuery>,
+    nexus: web::Data<Arc<Mutex<M8Nexus>>>,
+    retention: web::Data<SharedRetentionPolicy>,
+) -> Result<HttpResponse, ApiError> {
+    let policy = retention.lock().unwrap().clone();
+    let now = SystemTime::now();
     
     #[derive(Serialize)]
-    struct ContainerInfo {
+    struct Expiry {
         signature: String,
         content_type: String,
-        timestamp: String,
+        expires_at: u64,
+        expires_in_secs: u64,
+    }
+    
+    #[derive(Serialize)]
+    struct RetentionReport {
+        policy: crate::retention::RetentionPolicy,
+        sweep_interval_secs: u64,
+        total_expiring: usize,
+        upcoming: Vec<Expiry>,
     }
     
-    let container_list: Vec<ContainerInfo> = containers.into_iter()
-        .map(|(sig, content_type, timestamp)| ContainerInfo {
+    let expiring = nexus.lock().unwrap().expiring();
+    let total_expiring = expiring.len();
+    let upcoming = expiring.into_iter()
+        .take(query.limit.unwrap_or(100))
+        .map(|(sig, content_type, at)| Expiry {
             signature: hex::encode(sig),
             content_type: format!("{:?}", content_type),
-            timestamp: format!("{:?}", timestamp),
+            expires_at: at.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs(),
+            expires_in_secs: at.duration_since(now).unwrap_or_default().as_secs(),
         })
         .collect();
     
-    Ok(HttpResponse::Ok().json(container_list))
+    Ok(HttpResponse::Ok().json(RetentionReport {
+        policy,
+        sweep_interval_secs: crate::retention::SWEEP_INTERVAL.as_secs(),
+        total_expiring,
+        upcoming,
+    }))
+}
+
+/// PUT /retention/policy - Set or clear the TTL inherited by a content type
+pub async fn set_retention_policy(
+    rule: web::Json<RetentionRule>,
+    retention: web::Data<SharedRetentionPolicy>,
+) -> Result<HttpResponse, ApiError> {
+    let rule = rule.into_inner();
+    let mut policy = retention.lock().unwrap();
+    policy.set_ttl(rule.content_type, rule.ttl)
+        .map_err(|msg| ApiError::bad_request("invalid_retention_rule", msg))?;
+    
+    Ok(HttpResponse::Ok().json(policy.clone()))
 }
 
 /// GET /mem8/stats - Get nexus and MEM8 statistics
 pub async fn get_nexus_stats(
     nexus: web::Data<Arc<Mutex<M8Nexus>>>,
     mem8: web::Data<Arc<Mutex<mem8::Mem8>>>,
-) -> Result<HttpResponse, Error> {
+) -> Result<HttpResponse, ApiError> {
     let nexus_lock = nexus.lock().unwrap();
-    let nexus_stats = nexus_lock.stats();
+    let type_stats = nexus_lock.type_stats();
+    let original_bytes = type_stats.values().map(|t| t.original_bytes).sum::<u64>();
+    let stored_bytes = type_stats.values().map(|t| t.stored_bytes).sum::<u64>();
     
-    // Get basic stats from mem8 using available methods
     let mem8_lock = mem8.lock().unwrap();
-    
-    // Count total memories across all grids
-    let total_memories = 0; // Placeholder - mem8 doesn't expose a stats method
+    let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+    let recent_memories = mem8_lock.get_recent_memories(hour_ago).map(|recent| recent.len()).unwrap_or(0);
     
     Ok(HttpResponse::Ok().json(NexusStats {
-        total_containers: nexus_stats.len(),
-        type_counts: nexus_stats,
+        total_containers: nexus_lock.container_count(),
+        type_counts: nexus_lock.stats(),
+        type_stats,
+        compression: CompressionStats {
+            original_bytes,
+            stored_bytes,
+            saved_bytes: original_bytes.saturating_sub(stored_bytes),
+            ratio: if stored_bytes == 0 { 1.0 } else { original_bytes as f64 / stored_bytes as f64 },
+        },
         mem8_stats: Mem8Stats {
-            total_memories,
-            grid_dimensions: (10, 10), // Default grid size
+            total_memories: mem8_lock.get_stats().total_memories,
+            referenced_memories: nexus_lock.referenced_memories(),
+            recent_memories,
         },
     }))
 }
 
-/// SSE endpoint for real-time context sharing
+/// GET /events - Server-sent events for real-time context sharing.
+/// Every client gets every event; reconnecting with `Last-Event-ID` replays
+/// the buffered events after that ID first.
 pub async fn events_sse(
-    event_queue: web::Data<EventQueue>,
-    _req: HttpRequest,
-) -> Result<HttpResponse, Error> {
+    events: web::Data<SharedEventHub>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let last_event_id = req.headers().get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|id| id.trim().parse::<u64>().ok());
+    let hub = events.get_ref().clone();
+    // IDs restart at 1 with the server. An ID we haven't issued yet came from
+    // before a restart and would filter out every new event, so it is ignored.
+    let last_event_id = last_event_id.filter(|&id| id <= hub.latest_id());
+    
     let stream = async_stream::stream! {
-        let mut interval = interval(Duration::from_secs(2));
+        let (mut missed, mut receiver) = hub.subscribe(last_event_id);
+        let mut last_sent = last_event_id.unwrap_or(0);
+        let mut heartbeat = interval(HEARTBEAT_INTERVAL);
+        heartbeat.tick().await; // The first tick is immediate
         loop {
-            interval.tick().await;
-            let mut queue = event_queue.lock().unwrap();
-            while let Some(event) = queue.pop_front() {
-                yield Ok::<_, Error>(Bytes::from(format!("data: {}\n\n", event)));
+            for event in missed.drain(..) {
+                last_sent = event.id;
+                yield Ok::<_, Error>(Bytes::from(event.to_sse()));
+            }
+            let received = tokio::select! {
+                received = receiver.recv() => Some(received),
+                _ = heartbeat.tick() => None,
+            };
+            match received {
+                Some(Ok(event)) if event.id > last_sent => {
+                    last_sent = event.id;
+                    yield Ok(Bytes::from(event.to_sse()));
+                }
+                Some(Ok(_)) => {}
+                // Fell behind the channel: pick up again from the ring buffer
+                Some(Err(RecvError::Lagged(_))) => (missed, receiver) = hub.subscribe(Some(last_sent)),
+                Some(Err(RecvError::Closed)) => break,
+                None => yield Ok(Bytes::from_static(b": heartbeat\n\n")),
             }
         }
     };
     
-    Ok(HttpResponse::build(actix_web::http::StatusCode::OK)
+    Ok(HttpResponse::build(StatusCode::OK)
         .content_type("text/event-stream")
         .insert_header(("Cache-Control", "no-cache"))
         .streaming(stream))
 }
 
-/// WebSocket handler for auctioneer live feed
+/// Body of POST /battle/events: one battle event, tagged by `type`.
+/// Mirrors `BattleEventType`, with the battle's duration in seconds.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum BattleEventInput {
+    ContenderEnters {
+        ai_name: String,
+        specialty: String,
+        confidence: f32, // 0-1
+    },
+    TestAttempt {
+        ai_name: String,
+        test_name: String,
+        attempt_number: u32,
+        success: bool,
+    },
+    CodeChange {
+        ai_name: String,
+        file: String,
+        lines_changed: u32,
+        approach: String,
+    },
+    EpicMove {
+        ai_name: String,
+        move_description: String,
+        crowd_reaction: String,
+    },
+    BattleResult {
+        winner: String,
+        decisive_factor: String,
+        duration_secs: f64,
+    },
+}
+
+impl BattleEventInput {
+    fn into_event(self) -> Result<BattleEventType, ApiError> {
+        let invalid = |msg: &str| ApiError::bad_request("invalid_battle_event", msg);
+        let event = match self {
+            Self::ContenderEnters { ai_name, specialty, confidence } => {
+                if !(0.0..=1.0).contains(&confidence) {
+                    return Err(invalid("confidence must be between 0 and 1"));
+                }
+                BattleEventType::ContenderEnters { ai_name, specialty, confidence }
+            }
+            Self::TestAttempt { ai_name, test_name, attempt_number, success } => {
+                BattleEventType::TestAttempt { ai_name, test_name, attempt_number, success }
+            }
+            Self::CodeChange { ai_name, file, lines_changed, approach } => {
+                BattleEventType::CodeChange { ai_name, file, lines_changed, approach }
+            }
+            Self::EpicMove { ai_name, move_description, crowd_reaction } => {
+                BattleEventType::EpicMove { ai_name, move_description, crowd_reaction }
+            }
+            Self::BattleResult { winner, decisive_factor, duration_secs } => {
+                let duration = Duration::try_from_secs_f64(duration_secs)
+                    .map_err(|_| invalid("duration_secs must be a non-negative number of seconds"))?;
+                BattleEventType::BattleResult { winner, decisive_factor, duration }
+            }
+        };
+        match &event {
+            BattleEventType::ContenderEnters { ai_name, .. }
+            | BattleEventType::TestAttempt { ai_name, .. }
+            | BattleEventType::CodeChange { ai_name, .. }
+            | BattleEventType::EpicMove { ai_name, .. }
+            | BattleEventType::BattleResult { winner: ai_name, .. } if ai_name.trim().is_empty() => {
+                Err(invalid("The AI needs a name"))
+            }
+            _ => Ok(event),
+        }
+    }
+}
+
+/// Response of POST /battle/events
+#[derive(Serialize)]
+pub struct BattleEventResponse {
+    pub commentary: String,
+    pub excitement_level: u8, // 1-10
+    pub crowd_reaction: String,
+}
+
+/// POST /battle/events - Feed an AI coding battle event to the auctioneer.
+/// The commentary goes out on the live feed and comes back in the response.
+pub async fn post_battle_event(
+    body: web::Json<BattleEventInput>,
+    auctioneer: web::Data<Arc<Auctioneer>>,
+) -> Result<HttpResponse, ApiError> {
+    let event = body.into_inner().into_event()?;
+    let announced = auctioneer.announce_battle_event(event).await;
+    
+    Ok(HttpResponse::Ok().json(BattleEventResponse {
+        crowd_reaction: get_crowd_reaction(announced.excitement_level).to_string(),
+        commentary: announced.commentary,
+        excitement_level: announced.excitement_level,
+    }))
+}
+
+/// Body of GET and PUT /auctioneer/style
+#[derive(Serialize, Deserialize)]
+pub struct StyleSetting {
+    pub style: CommentaryStyle,
+}
+
+/// GET /auctioneer/style - The global commentary style
+pub async fn get_auctioneer_style(
+    auctioneer: web::Data<Arc<Auctioneer>>,
+) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().json(StyleSetting { style: auctioneer.style() }))
+}
+
+/// PUT /auctioneer/style - Change the global commentary style, for every
+/// session that hasn't picked its own
+pub async fn set_auctioneer_style(
+    body: web::Json<StyleSetting>,
+    auctioneer: web::Data<Arc<Auctioneer>>,
+) -> Result<HttpResponse, ApiError> {
+    auctioneer.set_style(body.style);
+    Ok(HttpResponse::Ok().json(StyleSetting { style: auctioneer.style() }))
+}
+
+/// WebSocket handler for auctioneer live feed.
+/// `/style {name}` picks this session's commentary style; `/style reset`
+/// goes back to the global one.
 pub async fn auctioneer_ws(
     req: HttpRequest,
     stream: web::Payload,
-    auctioneer_tx: web::Data<tokio::sync::mpsc::UnboundedSender<AuctionEvent>>,
-) -> Result<HttpResponse, Error> {
+    feed: web::Data<SharedLiveFeed>,
+) -> Result<HttpResponse, ApiError> {
     use actix_ws::Message;
     
-    let (response, mut session, mut stream) = actix_ws::handle(&req, stream)?;
+    let (response, mut session, mut stream) = actix_ws::handle(&req, stream)
+        .map_err(|e| ApiError::bad_request("websocket_handshake", e.to_string()))?;
     
-    // Create a channel to receive auctioneer events
-    let (_tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<AuctionEvent>();
+    // Every session hears the same auctioneer feed
+    let mut rx = feed.subscribe();
     
-    // Clone the sender for the spawned task
-    let auctioneer_tx_clone = auctioneer_tx.get_ref().clone();
+    // This session's own style, if it picked one
+    let session_style: Arc<Mutex<Option<CommentaryStyle>>> = Arc::new(Mutex::new(None));
     
     // Spawn task to handle incoming WebSocket messages
+    let style = session_style.clone();
+    let mut replies = session.clone();
     actix_web::rt::spawn(async move {
         while let Some(Ok(msg)) = stream.recv().await {
             match msg {
                 Message::Text(text) => {
                     // Handle commands like changing commentary style
-                    if text.starts_with("/style ") {
-                        let style_str = text.trim_start_matches("/style ");
-                        let _style = match style_str {
-                            "fast" => CommentaryStyle::FastTalking,
-                            "dramatic" => CommentaryStyle::Dramatic,
-                            "technical" => CommentaryStyle::Technical,
-                            "comedic" => CommentaryStyle::Comedic,
-                            "philosophical" => CommentaryStyle::Philosophical,
-                            _ => CommentaryStyle::FastTalking,
-                        };
-                        
-                        // Send style change event
-                        let _ = auctioneer_tx_clone.send(AuctionEvent::AuctioneerComment {
-                            message: format!("Switching to {} commentary style!", style_str),
-                            excitement_level: 5,
-                        });
+                    let Some(name) = text.strip_prefix("/style ") else {
+                        continue;
+                    };
+                    let reply = if name.trim() == "reset" {
+                        *style.lock().unwrap() = None;
+                        "Back to the house commentary style!".to_string()
+                    } else if let Some(picked) = CommentaryStyle::from_name(name) {
+                        *style.lock().unwrap() = Some(picked);
+                        format!("Switching to {} commentary style!", picked.name())
+                    } else {
+                        let names: Vec<_> = CommentaryStyle::ALL.iter().map(|style| style.name()).collect();
+                        format!("Unknown style '{}'. Try one of: {}", name.trim(), names.join(", "))
+                    };
+                    
+                    // Only this viewer hears about it; sequence 0 is outside the global feed
+                    let message = LiveFeedMessage {
+                        event: AuctionEvent::comment(reply, 5),
+                        timestamp: SystemTime::now(),
+                        sequence: 0,
+                    };
+                    if replies.text(serde_json::to_string(&message).unwrap_or_default()).await.is_err() {
+                        break;
                     }
                 }
                 Message::Close(_) => break,
@@ -460,16 +1972,26 @@ pub async fn auctioneer_ws(
     
     // Spawn task to send auctioneer events to WebSocket
     actix_web::rt::spawn(async move {
-        let mut sequence = 0u64;
-        while let Some(event) = rx.recv().await {
-            sequence += 1;
-            let message = serde_json::to_string(&crate::auctioneer::LiveFeedMessage {
-                event,
-                timestamp: SystemTime::now(),
-                sequence,
-            }).unwrap_or_default();
+        loop {
+            let message = match rx.recv().await {
+                Ok(message) => message,
+                // Too slow to keep up: skip ahead, the sequence numbers show the gap
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Auctioneer session fell behind, skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
             
-            if session.text(message).await.is_err() {
+            // Re-render commentary for sessions with their own style
+            let restyled = session_style.lock().unwrap()
+                .and_then(|style| message.event.in_style(style))
+                .map(|event| LiveFeedMessage { event, timestamp: message.timestamp, sequence: message.sequence });
+            let json = match restyled {
+                Some(restyled) => serde_json::to_string(&restyled),
+                None => serde_json::to_string(&*message),
+            };
+            if session.text(json.unwrap_or_default()).await.is_err() {
                 break;
             }
         }
@@ -479,21 +2001,39 @@ pub async fn auctioneer_ws(
     Ok(response)
 }
 
-// Add hex encoding module
-mod hex {
-    pub fn encode(bytes: impl AsRef<[u8]>) -> String {
-        bytes.as_ref().iter()
-            .map(|b| format!("{:02x}", b))
-            .collect()
+/// Parse a hex wave signature from a path or query parameter
+fn parse_signature(signature_hex: &str) -> Option<[u8; 32]> {
+    match hex::decode(signature_hex) {
+        Ok(bytes) if bytes.len() == 32 => {
+            let mut arr = [0u8; 32];
+            arr.copy_from_slice(&bytes);
+            Some(arr)
+        }
+        _ => None,
     }
+}
+
+/// Resolve the `parent` upload parameter into signatures of stored containers
+pub(crate) fn resolve_parents(
+    params: &UploadParams,
+    nexus: &Arc<Mutex<M8Nexus>>,
+) -> Result<Vec<[u8; 32]>, String> {
+    let raw = match params.parent.as_deref() {
+        Some(raw) if !raw.trim().is_empty() => raw,
+        _ => return Ok(Vec::new()),
+    };
     
-    pub fn decode(s: &str) -> Result<Vec<u8>, String> {
-        (0..s.len())
-            .step_by(2)
-            .map(|i| {
-                u8::from_str_radix(&s[i..i + 2], 16)
-                    .map_err(|e| e.to_string())
-            })
-            .collect()
+    let nexus_lock = nexus.lock().unwrap();
+    let mut parents = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let sig = parse_signature(part)
+            .ok_or_else(|| format!("Invalid parent signature: {}", part))?;
+        if nexus_lock.retrieve(&sig).is_none() {
+            return Err(format!("Parent container not found: {}", part));
+        }
+        if !parents.contains(&sig) {
+            parents.push(sig);
+        }
     }
-}
\ No newline at end of file
+    Ok(parents)
+}
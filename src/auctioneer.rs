@@ -9,6 +9,7 @@ use std::collections::{HashMap, VecDeque};
 use std::time::{SystemTime, Duration};
 use mem8::{Mem8, EmotionalContext};
 use crate::m8::{M8Container, M8ContentType};
+use crate::auctioneer_battles::{generate_battle_commentary, get_crowd_reaction, BattleEventType, CodingBattleEvent};
 use tokio::sync::mpsc;
 use serde::{Serialize, Deserialize};
 
@@ -57,9 +58,13 @@ pub enum AuctionEvent {
     AuctioneerComment {
         message: String,
         excitement_level: u8, // 1-10
+        /// What the comment is about, so sessions with their own style can
+        /// say it their way. Never sent to clients.
+        #[serde(skip)]
+        subject: Option<CommentarySubject>,
     },
     AIBattle {
-        event: crate::auctioneer_battles::CodingBattleEvent,
+        event: CodingBattleEvent,
     },
 }
 
@@ -69,11 +74,14 @@ pub struct Auctioneer {
     event_history: Arc<Mutex<VecDeque<AuctionEvent>>>,
     event_tx: mpsc::UnboundedSender<AuctionEvent>,
     excitement_threshold: f32,
-    commentary_style: CommentaryStyle,
+    commentary_style: Mutex<CommentaryStyle>, // Global style; sessions may pick their own
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CommentaryStyle {
+    #[default]
+    #[serde(rename = "fast")]
     FastTalking,     // Classic auctioneer rapid-fire
     Dramatic,        // Theater-style dramatic pauses
     Technical,       // Explains the quantum mechanics
@@ -81,6 +89,184 @@ pub enum CommentaryStyle {
     Philosophical,   // Deep thoughts about consciousness
 }
 
+impl CommentaryStyle {
+    pub const ALL: [CommentaryStyle; 5] = [
+        Self::FastTalking, Self::Dramatic, Self::Technical, Self::Comedic, Self::Philosophical,
+    ];
+
+    /// Name used by `/style {name}` and the style endpoint
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::FastTalking => "fast",
+            Self::Dramatic => "dramatic",
+            Self::Technical => "technical",
+            Self::Comedic => "comedic",
+            Self::Philosophical => "philosophical",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|style| style.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The commentary on a subject, in this style
+    pub fn comment_on(&self, subject: &CommentarySubject) -> String {
+        match subject {
+            CommentarySubject::Contribution { contributor_name, wave_strength, content_type } =>
+                self.comment_on_contribution(contributor_name, *wave_strength, content_type),
+            CommentarySubject::Entanglement { contributors: [first, second], correlation } =>
+                self.comment_on_entanglement(first, second, correlation * 100.0),
+            CommentarySubject::CrowdReaction { excitement } =>
+                self.comment_on_crowd(*excitement),
+        }
+    }
+
+    /// Commentary on a battle event in this style, with how exciting it is
+    pub fn comment_on_battle(&self, event: &BattleEventType) -> (String, u8) {
+        let (house, excitement) = generate_battle_commentary(event);
+        let summary = event.summary();
+        let commentary = match self {
+            CommentaryStyle::FastTalking => house,
+            CommentaryStyle::Dramatic =>
+                format!("*dramatic pause* ... {summary} ... *SHOUTS* THE ARENA TREMBLES!"),
+            CommentaryStyle::Technical =>
+                format!("Battle log: {summary}. Excitement index {excitement}/10."),
+            CommentaryStyle::Comedic =>
+                format!("{summary}! Somebody check the stack trace, \
+                        because that was either genius or a very confident bug!"),
+            CommentaryStyle::Philosophical =>
+                format!("{summary}. In every contest of minds, who truly wins, the coder or the code?"),
+        };
+        (commentary, excitement)
+    }
+
+    fn comment_on_contribution(&self, contributor_name: &str, wave_strength: f32, content_type: &M8ContentType) -> String {
+        let excitement = contribution_excitement(wave_strength);
+        
+        match self {
+            CommentaryStyle::FastTalking => {
+                match (excitement, content_type) {
+                    (8..=10, M8ContentType::Marqant) => 
+                        format!("HOLYMEMORY! {contributor_name} just dropped a QUANTUM COMPRESSED MARQANT! \
+                                Wave strength {wave_strength:.2} and climbing! Do I hear {:.2}? {:.2}? \
+                                Going to the quantum field in 3... 2...", wave_strength + 0.1, wave_strength + 0.2),
+                    (8..=10, _) => 
+                        format!("WE GOT A HOT ONE! {contributor_name} coming in STRONG at {wave_strength:.2}! \
+                                Can anyone match this intensity? Going once at {wave_strength:.2}!"),
+                    (5..=7, _) => 
+                        format!("Nice contribution from {contributor_name}! Solid {wave_strength:.2} on the wave meter! \
+                                Who's gonna bump it up? I see potential for entanglement here!"),
+                    _ => 
+                        format!("{contributor_name} adds to the quantum pool at {wave_strength:.2}. \
+                                Every wave counts in the memory nexus!")
+                }
+            },
+            CommentaryStyle::Dramatic => {
+                format!("*dramatic pause* ... {contributor_name} ... has entered the quantum realm ... \
+                        *whispers* wave strength {wave_strength:.2} ... *SHOUTS* MAGNIFICENT!")
+            },
+            CommentaryStyle::Technical => {
+                format!("Analyzing quantum signature from {contributor_name}: Wave amplitude {wave_strength:.2}, \
+                        Type: {:?}, Estimated coherence time: {:.2}ms. Fascinating quantum superposition detected!",
+                        content_type, wave_strength * 1000.0)
+            },
+            CommentaryStyle::Comedic => {
+                format!("Well well well, if it isn't {contributor_name} with a {wave_strength:.2}! \
+                        That's more entangled than my headphones after a day in my pocket! \
+                        Schrödinger's cat is definitely alive after seeing this one!")
+            },
+            CommentaryStyle::Philosophical => {
+                format!("In the grand tapestry of consciousness, {contributor_name} weaves a thread of {wave_strength:.2}. \
+                        What is memory but waves upon the ocean of mind? This {:?} speaks to the eternal now...",
+                        content_type)
+            }
+        }
+    }
+
+    fn comment_on_entanglement(&self, first: &str, second: &str, percent: f32) -> String {
+        match self {
+            CommentaryStyle::FastTalking =>
+                format!("🌀 QUANTUM ENTANGLEMENT DETECTED! {first} and {second} are vibrating at {percent:.1}% correlation! \
+                        This is RARE folks! Their waves are practically dancing together!"),
+            CommentaryStyle::Dramatic =>
+                format!("*gasps* ... {first} ... and {second} ... *whispers* {percent:.1}% correlated ... \
+                        *SHOUTS* ENTANGLED!"),
+            CommentaryStyle::Technical =>
+                format!("Entanglement detected between {first} and {second}: wave strength correlation {percent:.1}%, \
+                        above the 90% threshold. Their states can no longer be described independently."),
+            CommentaryStyle::Comedic =>
+                format!("{first} and {second} are {percent:.1}% correlated! \
+                        Get these two a room... or at least a shared Hilbert space!"),
+            CommentaryStyle::Philosophical =>
+                format!("{first} and {second}, {percent:.1}% alike. Are two minds that resonate so closely \
+                        truly separate, or one wave seen twice?"),
+        }
+    }
+
+    fn comment_on_crowd(&self, excitement: u8) -> String {
+        let reaction = get_crowd_reaction(excitement);
+        match self {
+            CommentaryStyle::FastTalking => format!("*The crowd reacts: {reaction}*"),
+            CommentaryStyle::Dramatic => format!("*a hush falls over the arena* ... and then ... {reaction}"),
+            CommentaryStyle::Technical => format!("Crowd response at excitement level {excitement}/10: {reaction}"),
+            CommentaryStyle::Comedic => format!("The crowd gives us {reaction}... and one guy who came for the free snacks!"),
+            CommentaryStyle::Philosophical => format!("And the crowd answers with {reaction}, as crowds always have."),
+        }
+    }
+}
+
+/// How exciting a contribution of this wave strength is, 0-10
+fn contribution_excitement(wave_strength: f32) -> u8 {
+    (wave_strength * 10.0).min(10.0) as u8
+}
+
+/// The facts behind a comment, so it can be said in any style
+#[derive(Debug, Clone)]
+pub enum CommentarySubject {
+    /// A new contribution to the nexus
+    Contribution {
+        contributor_name: String,
+        wave_strength: f32,
+        content_type: M8ContentType,
+    },
+    /// Two contributors whose wave strengths line up
+    Entanglement {
+        contributors: [String; 2],
+        correlation: f32,
+    },
+    /// The crowd after a battle event
+    CrowdReaction {
+        excitement: u8,
+    },
+}
+
+impl AuctionEvent {
+    /// A plain comment from the auctioneer
+    pub fn comment(message: impl Into<String>, excitement_level: u8) -> Self {
+        Self::AuctioneerComment { message: message.into(), excitement_level, subject: None }
+    }
+
+    /// A comment on a subject, in the given style
+    pub fn styled_comment(style: CommentaryStyle, subject: CommentarySubject, excitement_level: u8) -> Self {
+        Self::AuctioneerComment { message: style.comment_on(&subject), excitement_level, subject: Some(subject) }
+    }
+
+    /// This event as a session with its own commentary style should see it
+    pub fn in_style(&self, style: CommentaryStyle) -> Option<Self> {
+        match self {
+            Self::AuctioneerComment { excitement_level, subject: Some(subject), .. } =>
+                Some(Self::styled_comment(style, subject.clone(), *excitement_level)),
+            Self::AIBattle { event } => Some(Self::AIBattle {
+                event: CodingBattleEvent {
+                    commentary: style.comment_on_battle(&event.event_type).0,
+                    ..event.clone()
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
 impl Auctioneer {
     pub fn new(event_tx: mpsc::UnboundedSender<AuctionEvent>) -> Self {
         Self {
@@ -88,7 +274,7 @@ impl Auctioneer {
             event_history: Arc::new(Mutex::new(VecDeque::with_capacity(1000))),
             event_tx,
             excitement_threshold: 0.7,
-            commentary_style: CommentaryStyle::FastTalking,
+            commentary_style: Mutex::new(CommentaryStyle::default()),
         }
     }
 
@@ -134,6 +320,18 @@ impl Auctioneer {
         self.add_event(event.clone());
         let _ = self.event_tx.send(event);
         
+        // Strongly emotional contributions get their own moment in the spotlight
+        if emotional_impact > self.excitement_threshold {
+            let [valence, arousal, dominance] = crate::m8::emotional_bytes_to_vad(&container.header.emotional_context);
+            let spike = AuctionEvent::EmotionalSpike {
+                contributor: contributor_id.to_string(),
+                emotion: EmotionalContext { valence, arousal, dominance },
+                intensity: emotional_impact,
+            };
+            self.add_event(spike.clone());
+            let _ = self.event_tx.send(spike);
+        }
+        
         // Generate commentary
         self.generate_commentary(contributor_id, wave_strength, &container.header.content_type).await;
         
@@ -143,67 +341,29 @@ impl Auctioneer {
 
     /// Generate auctioneer commentary based on the contribution
     async fn generate_commentary(&self, contributor_id: &str, wave_strength: f32, content_type: &M8ContentType) {
-        let excitement = (wave_strength * 10.0).min(10.0) as u8;
         let contributor_name = self.contributors.lock().unwrap()
             .get(contributor_id)
             .map(|c| c.name.clone())
             .unwrap_or_else(|| contributor_id.to_string());
         
-        let message = match self.commentary_style {
-            CommentaryStyle::FastTalking => {
-                match (excitement, content_type) {
-                    (8..=10, M8ContentType::Marqant) => 
-                        format!("HOLYMEMORY! {contributor_name} just dropped a QUANTUM COMPRESSED MARQANT! \
-                                Wave strength {wave_strength:.2} and climbing! Do I hear {:.2}? {:.2}? \
-                                Going to the quantum field in 3... 2...", wave_strength + 0.1, wave_strength + 0.2),
-                    (8..=10, _) => 
-                        format!("WE GOT A HOT ONE! {contributor_name} coming in STRONG at {wave_strength:.2}! \
-                                Can anyone match this intensity? Going once at {wave_strength:.2}!"),
-                    (5..=7, _) => 
-                        format!("Nice contribution from {contributor_name}! Solid {wave_strength:.2} on the wave meter! \
-                                Who's gonna bump it up? I see potential for entanglement here!"),
-                    _ => 
-                        format!("{contributor_name} adds to the quantum pool at {wave_strength:.2}. \
-                                Every wave counts in the memory nexus!")
-                }
-            },
-            CommentaryStyle::Dramatic => {
-                format!("*dramatic pause* ... {contributor_name} ... has entered the quantum realm ... \
-                        *whispers* wave strength {wave_strength:.2} ... *SHOUTS* MAGNIFICENT!")
-            },
-            CommentaryStyle::Technical => {
-                format!("Analyzing quantum signature from {contributor_name}: Wave amplitude {wave_strength:.2}, \
-                        Type: {:?}, Estimated coherence time: {:.2}ms. Fascinating quantum superposition detected!",
-                        content_type, wave_strength * 1000.0)
-            },
-            CommentaryStyle::Comedic => {
-                format!("Well well well, if it isn't {contributor_name} with a {wave_strength:.2}! \
-                        That's more entangled than my headphones after a day in my pocket! \
-                        Schrödinger's cat is definitely alive after seeing this one!")
-            },
-            CommentaryStyle::Philosophical => {
-                format!("In the grand tapestry of consciousness, {contributor_name} weaves a thread of {wave_strength:.2}. \
-                        What is memory but waves upon the ocean of mind? This {:?} speaks to the eternal now...",
-                        content_type)
-            }
-        };
-        
-        let comment_event = AuctionEvent::AuctioneerComment { message, excitement_level: excitement };
+        let subject = CommentarySubject::Contribution { contributor_name, wave_strength, content_type: content_type.clone() };
+        let comment_event = AuctionEvent::styled_comment(self.style(), subject, contribution_excitement(wave_strength));
         let _ = self.event_tx.send(comment_event);
     }
 
     /// Check for quantum entanglement between contributors
     async fn check_for_quantum_events(&self) {
-        let contributors = self.contributors.lock().unwrap();
-        
-        // Find recent active contributors
+        // Find recent active contributors. Cloned so the lock is released
+        // before update_leaderboard takes it again.
         let now = SystemTime::now();
-        let recent: Vec<_> = contributors.values()
+        let recent: Vec<_> = self.contributors.lock().unwrap()
+            .values()
             .filter(|c| {
                 c.last_seen.duration_since(SystemTime::UNIX_EPOCH).unwrap()
                     .saturating_sub(now.duration_since(SystemTime::UNIX_EPOCH).unwrap())
                     < Duration::from_secs(30)
             })
+            .cloned()
             .collect();
         
         // Check for quantum entanglement (similar wave strengths)
@@ -218,13 +378,11 @@ impl Auctioneer {
                     let _ = self.event_tx.send(event);
                     
                     // Special commentary for entanglement
-                    let message = format!("🌀 QUANTUM ENTANGLEMENT DETECTED! {} and {} are vibrating at {:.1}% correlation! \
-                                         This is RARE folks! Their waves are practically dancing together!",
-                                         recent[i].name, recent[j].name, correlation * 100.0);
-                    let _ = self.event_tx.send(AuctionEvent::AuctioneerComment {
-                        message,
-                        excitement_level: 10,
-                    });
+                    let subject = CommentarySubject::Entanglement {
+                        contributors: [recent[i].name.clone(), recent[j].name.clone()],
+                        correlation,
+                    };
+                    let _ = self.event_tx.send(AuctionEvent::styled_comment(self.style(), subject, 10));
                 }
             }
         }
@@ -314,18 +472,27 @@ impl Auctioneer {
             .collect()
     }
 
-    /// Change commentary style
-    pub fn set_style(&mut self, style: CommentaryStyle) {
-        self.commentary_style = style;
+    /// Current global commentary style
+    pub fn style(&self) -> CommentaryStyle {
+        *self.commentary_style.lock().unwrap()
     }
     
-    /// Handle AI coding battle events
-    pub async fn announce_battle_event(&self, battle_type: crate::auctioneer_battles::BattleEventType) {
-        let (commentary, excitement) = crate::auctioneer_battles::generate_battle_commentary(&battle_type);
-        let crowd_reaction = crate::auctioneer_battles::get_crowd_reaction(excitement);
+    /// Change the global commentary style and tell everyone watching
+    pub fn set_style(&self, style: CommentaryStyle) {
+        *self.commentary_style.lock().unwrap() = style;
+        let _ = self.event_tx.send(AuctionEvent::comment(format!("Switching to {} commentary style!", style.name()), 5));
+    }
+    
+    /// Handle AI coding battle events, returning the event as announced
+    pub async fn announce_battle_event(
+        &self,
+        battle_type: BattleEventType,
+    ) -> CodingBattleEvent {
+        let style = self.style();
+        let (commentary, excitement) = style.comment_on_battle(&battle_type);
         
         // Create the battle event
-        let battle_event = crate::auctioneer_battles::CodingBattleEvent {
+        let battle_event = CodingBattleEvent {
             timestamp: SystemTime::now(),
             event_type: battle_type,
             excitement_level: excitement,
@@ -333,13 +500,15 @@ impl Auctioneer {
         };
         
         // Send the battle event
-        let _ = self.event_tx.send(AuctionEvent::AIBattle { event: battle_event });
+        let _ = self.event_tx.send(AuctionEvent::AIBattle { event: battle_event.clone() });
         
         // Add crowd reaction as a follow-up comment
-        let _ = self.event_tx.send(AuctionEvent::AuctioneerComment {
-            message: format!("*The crowd reacts: {}*", crowd_reaction),
-            excitement_level: excitement.saturating_sub(2),
-        });
+        let _ = self.event_tx.send(AuctionEvent::styled_comment(
+            style,
+            CommentarySubject::CrowdReaction { excitement },
+            excitement.saturating_sub(2),
+        ));
+        battle_event
     }
 }
 
@@ -349,4 +518,86 @@ pub struct LiveFeedMessage {
     pub event: AuctionEvent,
     pub timestamp: SystemTime,
     pub sequence: u64,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_switching() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let auctioneer = Auctioneer::new(tx);
+        assert_eq!(CommentaryStyle::from_name("Philosophical"), Some(CommentaryStyle::Philosophical));
+        assert_eq!(CommentaryStyle::from_name("mumbling"), None);
+
+        // Global switch, announced on the feed
+        auctioneer.set_style(CommentaryStyle::Dramatic);
+        assert_eq!(auctioneer.style(), CommentaryStyle::Dramatic);
+        assert!(matches!(rx.try_recv(), Ok(AuctionEvent::AuctioneerComment { .. })));
+
+        // A session with its own style gets contribution commentary re-rendered
+        let subject = CommentarySubject::Contribution {
+            contributor_name: "QuantumQubit42".to_string(),
+            wave_strength: 0.5,
+            content_type: M8ContentType::Language,
+        };
+        let event = AuctionEvent::styled_comment(CommentaryStyle::Dramatic, subject, 5);
+        let Some(AuctionEvent::AuctioneerComment { message, .. }) = event.in_style(CommentaryStyle::Philosophical) else {
+            panic!("contribution commentary should be restyled");
+        };
+        assert!(message.starts_with("In the grand tapestry of consciousness, QuantumQubit42"));
+
+        // ...and so does entanglement commentary
+        let subject = CommentarySubject::Entanglement {
+            contributors: ["QuantumQubit42".to_string(), "SpinningBoson7".to_string()],
+            correlation: 0.95,
+        };
+        let event = AuctionEvent::styled_comment(CommentaryStyle::Dramatic, subject, 10);
+        let Some(AuctionEvent::AuctioneerComment { message, .. }) = event.in_style(CommentaryStyle::Technical) else {
+            panic!("entanglement commentary should be restyled");
+        };
+        assert!(message.starts_with("Entanglement detected between QuantumQubit42 and SpinningBoson7: wave strength correlation 95.0%"));
+        assert!(AuctionEvent::comment("Sold!", 5).in_style(CommentaryStyle::Comedic).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_battle_event_is_announced() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let auctioneer = Auctioneer::new(tx);
+        let announced = auctioneer.announce_battle_event(crate::auctioneer_battles::BattleEventType::TestAttempt {
+            ai_name: "DeepSeek".to_string(),
+            test_name: "memory_management_test".to_string(),
+            attempt_number: 1,
+            success: true,
+        }).await;
+
+        assert!(announced.commentary.contains("DeepSeek"));
+        assert!(matches!(rx.try_recv(), Ok(AuctionEvent::AIBattle { event }) if event.commentary == announced.commentary));
+        assert!(matches!(rx.try_recv(), Ok(AuctionEvent::AuctioneerComment { message, .. }) if message.starts_with("*The crowd reacts")));
+
+        // The global style applies to battles and the crowd too...
+        auctioneer.set_style(CommentaryStyle::Technical);
+        let _ = rx.try_recv();
+        let announced = auctioneer.announce_battle_event(crate::auctioneer_battles::BattleEventType::BattleResult {
+            winner: "DeepSeek".to_string(),
+            decisive_factor: "memory safety".to_string(),
+            duration: Duration::from_secs(42),
+        }).await;
+        assert_eq!(announced.commentary, "Battle log: DeepSeek wins after 42 seconds, thanks to memory safety. Excitement index 10/10.");
+        let Ok(battle) = rx.try_recv() else { panic!("battle event should be sent") };
+        let Ok(crowd) = rx.try_recv() else { panic!("crowd reaction should be sent") };
+        assert!(matches!(&crowd, AuctionEvent::AuctioneerComment { message, .. } if message.starts_with("Crowd response at excitement level 10/10")));
+
+        // ...and a session with its own style hears them its way
+        let Some(AuctionEvent::AIBattle { event }) = battle.in_style(CommentaryStyle::Philosophical) else {
+            panic!("battle commentary should be restyled");
+        };
+        assert!(event.commentary.starts_with("DeepSeek wins after 42 seconds") && event.commentary.ends_with("the coder or the code?"));
+        assert_eq!(event.excitement_level, 10);
+        let Some(AuctionEvent::AuctioneerComment { message, .. }) = crowd.in_style(CommentaryStyle::FastTalking) else {
+            panic!("crowd reaction should be restyled");
+        };
+        assert_eq!(message, "*The crowd reacts: *QUANTUM PANDEMONIUM**");
+    }
+}
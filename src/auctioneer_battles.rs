@@ -47,6 +47,24 @@ pub enum BattleEventType {
     },
 }
 
+impl BattleEventType {
+    /// What happened, in plain words, for commentary styles to dress up
+    pub fn summary(&self) -> String {
+        match self {
+            Self::ContenderEnters { ai_name, specialty, confidence } =>
+                format!("{} enters the arena, specializing in {} at {:.0}% confidence", ai_name, specialty, confidence * 100.0),
+            Self::TestAttempt { ai_name, test_name, attempt_number, success } =>
+                format!("{} {} {} on attempt #{}", ai_name, if *success { "passes" } else { "fails" }, test_name, attempt_number),
+            Self::CodeChange { ai_name, file, lines_changed, approach } =>
+                format!("{} changes {} lines in {} with {}", ai_name, lines_changed, file, approach),
+            Self::EpicMove { ai_name, move_description, .. } =>
+                format!("{} pulls off {}", ai_name, move_description),
+            Self::BattleResult { winner, decisive_factor, duration } =>
+                format!("{} wins after {} seconds, thanks to {}", winner, duration.as_secs(), decisive_factor),
+        }
+    }
+}
+
 /// Generate epic battle commentary
 pub fn generate_battle_commentary(event: &BattleEventType) -> (String, u8) {
     match event {
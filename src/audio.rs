@@ -0,0 +1,184 @@
+//! Audio memory ingestion for WAV files
+//!
+//! "Every memory has a frequency. Some of them are in B flat." - Trish
+//!
+//! Features are computed locally: duration, sample rate, an RMS energy
+//! envelope and the dominant frequency found with a radix-2 FFT.
+
+use anyhow::Result;
+use std::f32::consts::PI;
+use std::io::Cursor;
+
+/// Number of segments in the RMS energy envelope
+pub const ENVELOPE_SEGMENTS: usize = 32;
+
+/// Largest FFT window used to find the dominant frequency
+const MAX_FFT_SIZE: usize = 8192;
+
+/// Features extracted from an uploaded WAV file
+#[derive(Debug, Clone)]
+pub struct AudioFeatures {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_secs: f32,
+    pub rms: f32, // Overall RMS energy, 0-1
+    pub rms_envelope: Vec<f32>, // RMS per segment, 0-1
+    pub dominant_frequency: f32, // Hz
+    pub dominant_phase: f32, // Radians, phase of the dominant FFT bin
+}
+
+impl AudioFeatures {
+    /// Wave parameters used to register the audio in MEM8
+    pub fn wave_parameters(&self) -> (f32, f32, f32) {
+        (self.rms, self.dominant_frequency, self.dominant_phase)
+    }
+}
+
+/// Decode a WAV file and extract its features
+pub fn analyze(bytes: &[u8]) -> Result<AudioFeatures> {
+    let mut reader = hound::WavReader::new(Cursor::new(bytes))?;
+    let spec = reader.spec();
+    let channels = spec.channels.max(1) as usize;
+
+    let interleaved: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.samples::<i32>()
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    // Mix down to mono
+    let mono: Vec<f32> = interleaved.chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+    if mono.is_empty() {
+        return Err(anyhow::anyhow!("WAV file contains no samples"));
+    }
+
+    let segment_len = mono.len().div_ceil(ENVELOPE_SEGMENTS);
+    let rms_envelope = mono.chunks(segment_len).map(rms).collect();
+    let (dominant_frequency, dominant_phase) = dominant_frequency(&mono, spec.sample_rate);
+
+    Ok(AudioFeatures {
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        duration_secs: mono.len() as f32 / spec.sample_rate as f32,
+        rms: rms(&mono),
+        rms_envelope,
+        dominant_frequency,
+        dominant_phase,
+    })
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Strongest frequency (Hz) and its phase, from a Hann-windowed FFT over the
+/// loudest power-of-two window of the signal
+fn dominant_frequency(samples: &[f32], sample_rate: u32) -> (f32, f32) {
+    let mut size = MAX_FFT_SIZE;
+    while size > samples.len() {
+        size /= 2;
+    }
+    if size < 4 {
+        return (0.0, 0.0);
+    }
+
+    let start = samples.chunks_exact(size)
+        .enumerate()
+        .max_by(|a, b| rms(a.1).total_cmp(&rms(b.1)))
+        .map(|(i, _)| i * size)
+        .unwrap_or(0);
+
+    let mut re: Vec<f32> = samples[start..start + size].iter()
+        .enumerate()
+        .map(|(i, s)| s * 0.5 * (1.0 - (2.0 * PI * i as f32 / (size - 1) as f32).cos()))
+        .collect();
+    let mut im = vec![0.0; size];
+    fft(&mut re, &mut im);
+
+    // Skip the DC bin; only the first half of the spectrum is unique
+    let (bin, _) = (1..size / 2)
+        .map(|k| (k, re[k] * re[k] + im[k] * im[k]))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0));
+
+    (bin as f32 * sample_rate as f32 / size as f32, im[bin].atan2(re[bin]))
+}
+
+/// In-place iterative radix-2 Cooley-Tukey FFT; `re.len()` must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_sine_wav() {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+            for i in 0..8000 {
+                let t = i as f32 / 8000.0;
+                let sample = (2.0 * PI * 440.0 * t).sin() * 0.5;
+                writer.write_sample((sample * i16::MAX as f32) as i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        let bytes = cursor.into_inner();
+        assert_eq!(crate::mime::sniff(&bytes, "sine"), "audio/wav");
+
+        let features = analyze(&bytes).unwrap();
+        assert_eq!(features.sample_rate, 8000);
+        assert!((features.duration_secs - 1.0).abs() < 0.01);
+        assert_eq!(features.rms_envelope.len(), ENVELOPE_SEGMENTS);
+        assert!((features.rms - 0.5 / 2f32.sqrt()).abs() < 0.01);
+        // Bin width is 8000 / 4096 ≈ 2 Hz
+        assert!((features.dominant_frequency - 440.0).abs() < 4.0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Lexicon-based emotional context inference for text and markdown
+//!
+//! "Words carry waves. Some of them are tsunamis." - Hue
+//!
+//! Every word in a small built-in lexicon has a valence, arousal and
+//! dominance score in [-1, 1]. Text is scored by averaging the words it
+//! contains, with negation ("not happy") flipping and softening valence and
+//! intensifiers ("very angry") strengthening the next word. Nothing leaves
+//! the machine.
+
+use mem8::EmotionalContext;
+
+/// (word, valence, arousal, dominance)
+const LEXICON: &[(&str, f32, f32, f32)] = &[
+    // Joy and warmth
+    ("love", 0.9, 0.5, 0.4),
+    ("loved", 0.9, 0.5, 0.4),
+    ("happy", 0.8, 0.4, 0.4),
+    ("joy", 0.9, 0.6, 0.5),
+    ("delight", 0.8, 0.5, 0.4),
+    ("wonderful", 0.8, 0.5, 0.4),
+    ("great", 0.7, 0.4, 0.4),
+    ("good", 0.6, 0.2, 0.3),
+    ("nice", 0.5, 0.1, 0.2),
+    ("beautiful", 0.8, 0.4, 0.3),
+    ("amazing", 0.8, 0.7, 0.4),
+    ("awesome", 0.8, 0.7, 0.5),
+    ("thanks", 0.6, 0.2, 0.2),
+    ("thank", 0.6, 0.2, 0.2),
+    ("fun", 0.7, 0.6, 0.4),
+    ("hope", 0.5, 0.3, 0.1),
+    ("proud", 0.7, 0.5, 0.7),
+    ("win", 0.7, 0.6, 0.7),
+    ("success", 0.7, 0.5, 0.7),
+    ("calm", 0.5, -0.6, 0.3),
+    ("peace", 0.6, -0.6, 0.3),
+    ("relaxed", 0.6, -0.7, 0.3),
+    ("safe", 0.5, -0.4, 0.4),
+    // Excitement
+    ("excited", 0.7, 0.9, 0.4),
+    ("thrilled", 0.8, 0.9, 0.5),
+    ("wow", 0.6, 0.8, 0.3),
+    ("urgent", -0.2, 0.8, 0.2),
+    ("surprise", 0.3, 0.8, 0.0),
+    // Anger and power
+    ("angry", -0.7, 0.8, 0.5),
+    ("anger", -0.7, 0.8, 0.5),
+    ("furious", -0.8, 0.9, 0.6),
+    ("hate", -0.9, 0.7, 0.4),
+    ("annoyed", -0.5, 0.5, 0.2),
+    ("frustrated", -0.6, 0.6, -0.1),
+    ("rage", -0.8, 0.9, 0.6),
+    // Fear and helplessness
+    ("afraid", -0.7, 0.7, -0.7),
+    ("fear", -0.7, 0.7, -0.6),
+    ("scared", -0.7, 0.7, -0.7),
+    ("terrified", -0.9, 0.9, -0.8),
+    ("anxious", -0.6, 0.7, -0.5),
+    ("worried", -0.5, 0.5, -0.4),
+    ("panic", -0.8, 0.9, -0.7),
+    ("helpless", -0.7, 0.2, -0.9),
+    ("lost", -0.5, 0.1, -0.6),
+    // Sadness
+    ("sad", -0.7, -0.3, -0.4),
+    ("sorry", -0.4, -0.1, -0.4),
+    ("lonely", -0.7, -0.3, -0.5),
+    ("tired", -0.4, -0.7, -0.3),
+    ("bored", -0.4, -0.7, -0.2),
+    ("cry", -0.7, 0.3, -0.5),
+    ("grief", -0.8, -0.1, -0.5),
+    ("miss", -0.4, -0.1, -0.3),
+    // Trouble
+    ("bad", -0.6, 0.2, -0.2),
+    ("terrible", -0.8, 0.5, -0.3),
+    ("awful", -0.8, 0.5, -0.3),
+    ("broken", -0.6, 0.3, -0.4),
+    ("fail", -0.6, 0.4, -0.5),
+    ("failed", -0.6, 0.4, -0.5),
+    ("error", -0.5, 0.4, -0.3),
+    ("bug", -0.4, 0.3, -0.2),
+    ("crash", -0.6, 0.7, -0.4),
+    ("pain", -0.8, 0.6, -0.5),
+];
+
+/// Words that flip the valence of the word right after them
+const NEGATORS: &[&str] = &["not", "no", "never", "dont", "don't", "isnt", "isn't", "wasnt", "wasn't", "without"];
+
+/// Words that strengthen the word right after them
+const INTENSIFIERS: &[&str] = &["very", "really", "so", "extremely", "totally", "super", "incredibly"];
+
+/// Infer valence/arousal/dominance from text. Text without any lexicon words
+/// is neutral; a handful of hits counts for less than a consistent tone.
+pub fn analyze(text: &str) -> EmotionalContext {
+    let mut sums = [0.0f32; 3];
+    let mut matched = 0usize;
+    let mut negated = false;
+    let mut boost = 1.0f32;
+
+    for word in text.split(|c: char| !(c.is_alphanumeric() || c == '\'')) {
+        let word = word.trim_matches('\'').to_lowercase();
+        if word.is_empty() {
+            continue;
+        }
+        if NEGATORS.contains(&word.as_str()) {
+            negated = true;
+            continue;
+        }
+        if INTENSIFIERS.contains(&word.as_str()) {
+            boost = 1.5;
+            continue;
+        }
+
+        if let Some(&(_, valence, arousal, dominance)) = LEXICON.iter().find(|(w, ..)| *w == word) {
+            // "not happy" is mildly unhappy, not the opposite of happy
+            let valence = if negated { -valence * 0.5 } else { valence };
+            sums[0] += valence * boost;
+            sums[1] += arousal * boost;
+            sums[2] += dominance * boost;
+            matched += 1;
+        }
+        negated = false;
+        boost = 1.0;
+    }
+
+    if matched == 0 {
+        return EmotionalContext { valence: 0.0, arousal: 0.0, dominance: 0.0 };
+    }
+
+    let confidence = matched as f32 / (matched as f32 + 1.0);
+    let [valence, arousal, dominance] = sums.map(|sum| (sum / matched as f32 * confidence).clamp(-1.0, 1.0));
+    EmotionalContext { valence, arousal, dominance }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_tone() {
+        let neutral = analyze("The container stores 42 bytes.");
+        assert_eq!((neutral.valence, neutral.arousal, neutral.dominance), (0.0, 0.0, 0.0));
+
+        let joyful = analyze("I love this, what a wonderful **amazing** day!");
+        assert!(joyful.valence > 0.5);
+
+        let scared = analyze("I'm terrified and anxious, everything failed.");
+        assert!(scared.valence < -0.4);
+        assert!(scared.arousal > 0.4);
+        assert!(scared.dominance < -0.3);
+
+        // Negation softens and flips; intensifiers strengthen
+        assert!(analyze("not happy").valence < 0.0);
+        assert!(analyze("very happy").valence > analyze("happy").valence);
+    }
+}
@@ -0,0 +1,182 @@
+//! One error model for the whole API
+//!
+//! "An error without a code is just a sad noise." - Trish
+//!
+//! Every failing request gets the same JSON body:
+//! `{"code": "container_not_found", "message": "...", "request_id": "..."}`.
+//! Codes are stable and meant for machines; messages are for humans and may
+//! change. The request ID matches the `X-Request-Id` response header.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::idempotency::KeyReused;
+use crate::ingest::IngestError;
+
+/// Header carrying the request ID, honoured on requests and set on every response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// JSON body of every error response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    pub request_id: Option<String>,
+}
+
+/// An API failure: HTTP status, machine-readable code and message
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self { status, code, message: message.into() }
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    pub fn internal(e: impl std::fmt::Display) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string())
+    }
+
+    pub fn invalid_signature() -> Self {
+        Self::bad_request("invalid_signature", "Invalid signature format")
+    }
+
+    pub fn container_not_found() -> Self {
+        Self::not_found("container_not_found", "Container not found")
+    }
+
+    /// The body as it goes out, stamped with the current request ID
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            code: self.code.to_string(),
+            message: self.message.clone(),
+            request_id: current_request_id(),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status).json(self.body())
+    }
+}
+
+impl From<IngestError> for ApiError {
+    fn from(e: IngestError) -> Self {
+        match e {
+            IngestError::ConsentRequired(_) => Self::new(StatusCode::FORBIDDEN, "consent_required", e.to_string()),
+            IngestError::Invalid(message) => Self::bad_request("invalid_upload", message),
+            IngestError::InProgress => Self::new(StatusCode::CONFLICT, "upload_in_progress", e.to_string()),
+            IngestError::Internal(message) => Self::internal(message),
+        }
+    }
+}
+
+impl From<KeyReused> for ApiError {
+    fn from(e: KeyReused) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "idempotency_key_reused", e.to_string())
+    }
+}
+
+impl From<actix_multipart::MultipartError> for ApiError {
+    fn from(e: actix_multipart::MultipartError) -> Self {
+        Self::bad_request("invalid_multipart", e.to_string())
+    }
+}
+
+impl From<actix_web::error::PayloadError> for ApiError {
+    fn from(e: actix_web::error::PayloadError) -> Self {
+        Self::bad_request("invalid_body", e.to_string())
+    }
+}
+
+/// ID of the request being handled, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Middleware body for `App::wrap_fn`: gives each request an ID (the client's
+/// `X-Request-Id` if it sent a sane one), makes it visible to error responses
+/// and echoes it in the response headers.
+pub fn with_request_id<S, B>(
+    req: ServiceRequest,
+    service: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    let id = req.headers().get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(new_request_id);
+    let response = service.call(req);
+
+    REQUEST_ID.scope(id.clone(), async move {
+        let mut response = response.await?;
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+        }
+        Ok(response)
+    })
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Unique per process run: start time plus a counter
+fn new_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    lazy_static::lazy_static! {
+        static ref BOOT: u64 = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    }
+    format!("{:x}-{:06}", *BOOT, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_error_body_carries_request_id() {
+        let error = ApiError::container_not_found();
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        assert!(error.body().request_id.is_none());
+
+        let body = REQUEST_ID.scope("abc-1".to_string(), async { error.body() }).await;
+        assert_eq!(body.code, "container_not_found");
+        assert_eq!(body.request_id.as_deref(), Some("abc-1"));
+
+        assert!(is_valid_request_id("3f9a-000001"));
+        assert!(!is_valid_request_id("no spaces please"));
+        assert_ne!(new_request_id(), new_request_id());
+    }
+}
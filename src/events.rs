@@ -0,0 +1,251 @@
+//! Event hub behind GET /events
+//!
+//! "Everyone at the party should hear the toast, not just whoever is closest." - Trish
+//!
+//! Every nexus event is published once and broadcast to every connected SSE
+//! client. The last `EVENT_BUFFER` events are kept in a ring buffer with
+//! increasing IDs, so a client that reconnects with `Last-Event-ID` gets what
+//! it missed. Nothing else is retained, however long nobody is listening.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::hex;
+use crate::jobs::{Job, JobStatus};
+use crate::m8::{M8Container, M8ContentType};
+
+/// How many past events are kept for replay
+pub const EVENT_BUFFER: usize = 1024;
+
+/// How often an idle SSE stream gets a heartbeat comment
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Something that happened in the nexus. The variant name is the SSE `event:` name.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NexusEvent {
+    ContainerStored {
+        signature: String,
+        content_type: M8ContentType,
+        name: String, // Filename, or the kind of upload
+        memory_ids: Vec<u64>,
+        metadata: HashMap<String, String>,
+    },
+    ContainerDeleted {
+        signature: String,
+        content_type: M8ContentType,
+        unreferenced_memory_ids: Vec<u64>,
+    },
+    ContainerExpired {
+        signature: String,
+        content_type: M8ContentType,
+        unreferenced_memory_ids: Vec<u64>,
+    },
+    MetadataUpdated {
+        signature: String,
+        keys: Vec<String>,
+    },
+    BatchCompleted {
+        records: usize,
+        failed: usize,
+    },
+    JobUpdated {
+        job: Job,
+    },
+}
+
+impl NexusEvent {
+    pub fn container_stored(container: &M8Container, name: &str) -> Self {
+        Self::ContainerStored {
+            signature: hex::encode(container.wave_signature),
+            content_type: container.header.content_type.clone(),
+            name: name.to_string(),
+            memory_ids: container.header.memory_ids.clone(),
+            metadata: container.header.metadata.clone(),
+        }
+    }
+
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ContainerStored { .. } => "container_stored",
+            Self::ContainerDeleted { .. } => "container_deleted",
+            Self::ContainerExpired { .. } => "container_expired",
+            Self::MetadataUpdated { .. } => "metadata_updated",
+            Self::BatchCompleted { .. } => "batch_completed",
+            Self::JobUpdated { .. } => "job_updated",
+        }
+    }
+
+    /// One-line summary for humans
+    pub fn message(&self) -> String {
+        match self {
+            Self::ContainerStored { content_type, name, memory_ids, metadata, .. } => {
+                let meta = |key: &str| metadata.get(key).map(String::as_str).unwrap_or("?");
+                match content_type {
+                    M8ContentType::Marqant => format!("Marqant uploaded: {} (compression: {}x)", name, meta("compression_ratio")),
+                    M8ContentType::Language => format!("Text uploaded: {} bytes", meta("length")),
+                    M8ContentType::WavePattern => format!("Wave pattern uploaded: {} waves", meta("wave_count")),
+                    M8ContentType::Compound => format!("Compound bound: {} memories", memory_ids.len()),
+                    other => format!("{:?} uploaded: {}", other, name),
+                }
+            }
+            Self::ContainerDeleted { signature, unreferenced_memory_ids, .. } => {
+                format!("Container deleted: {} ({} memories now unreferenced)", signature, unreferenced_memory_ids.len())
+            }
+            Self::ContainerExpired { signature, content_type, unreferenced_memory_ids } => format!(
+                "Container expired: {} ({:?}, {} memories now unreferenced)",
+                signature, content_type, unreferenced_memory_ids.len()
+            ),
+            Self::MetadataUpdated { signature, keys } => format!("Metadata updated: {} ({} keys)", signature, keys.len()),
+            Self::BatchCompleted { records, failed } => format!("Batch uploaded: {} records ({} failed)", records, failed),
+            Self::JobUpdated { job } => match (job.status, &job.progress, &job.error) {
+                (JobStatus::Queued, _, _) => format!("Job {} queued: {}", job.id, job.kind),
+                (JobStatus::Running, Some(progress), _) => {
+                    format!("Job {} progress: {}/{}", job.id, progress.done, progress.total)
+                }
+                (JobStatus::Running, None, _) => format!("Job {} running", job.id),
+                (JobStatus::Completed, _, _) => format!("Job {} completed", job.id),
+                (JobStatus::Failed, _, error) => format!("Job {} failed: {}", job.id, error.as_deref().unwrap_or("unknown error")),
+            },
+        }
+    }
+}
+
+/// A published event with its place in the stream
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub id: u64,
+    pub timestamp: SystemTime,
+    pub event: NexusEvent,
+}
+
+/// `data:` payload of an SSE event
+#[derive(Serialize)]
+struct EventData<'a> {
+    id: u64,
+    timestamp: String, // RFC 3339
+    message: String,
+    #[serde(flatten)]
+    event: &'a NexusEvent,
+}
+
+impl Event {
+    /// The event as an SSE frame: `id:`, `event:` and a JSON `data:` line
+    pub fn to_sse(&self) -> String {
+        let data = EventData {
+            id: self.id,
+            timestamp: humantime::format_rfc3339_millis(self.timestamp).to_string(),
+            message: self.event.message(),
+            event: &self.event,
+        };
+        let json = serde_json::to_string(&data).unwrap_or_default();
+        format!("id: {}\nevent: {}\ndata: {}\n\n", self.id, self.event.name(), json)
+    }
+}
+
+struct Replay {
+    next_id: u64,
+    events: VecDeque<Event>,
+}
+
+/// Broadcasts events to every subscriber and remembers the latest ones
+pub struct EventHub {
+    replay: Mutex<Replay>,
+    sender: broadcast::Sender<Event>,
+    capacity: usize,
+}
+
+pub type SharedEventHub = Arc<EventHub>;
+
+impl EventHub {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            replay: Mutex::new(Replay { next_id: 1, events: VecDeque::with_capacity(capacity) }),
+            sender,
+            capacity,
+        }
+    }
+
+    /// Publish an event to every subscriber, returning its ID
+    pub fn publish(&self, event: NexusEvent) -> u64 {
+        let mut replay = self.replay.lock().unwrap();
+        let event = Event { id: replay.next_id, timestamp: SystemTime::now(), event };
+        replay.next_id += 1;
+        if replay.events.len() == self.capacity {
+            replay.events.pop_front();
+        }
+        replay.events.push_back(event.clone());
+
+        // Sent under the lock so subscribe() never sees an event twice or not at all.
+        // No subscribers is fine: the ring buffer has it.
+        let _ = self.sender.send(event.clone());
+        event.id
+    }
+
+    /// ID of the most recently published event, 0 if there is none yet
+    pub fn latest_id(&self) -> u64 {
+        self.replay.lock().unwrap().next_id - 1
+    }
+
+    /// Subscribe to new events, along with the buffered ones after `last_event_id`
+    pub fn subscribe(&self, last_event_id: Option<u64>) -> (Vec<Event>, broadcast::Receiver<Event>) {
+        let replay = self.replay.lock().unwrap();
+        let missed = match last_event_id {
+            Some(last) => replay.events.iter().filter(|event| event.id > last).cloned().collect(),
+            None => Vec::new(),
+        };
+        (missed, self.sender.subscribe())
+    }
+
+    /// The buffered events, oldest first
+    #[cfg(test)]
+    pub fn recent(&self) -> Vec<Event> {
+        self.replay.lock().unwrap().events.iter().cloned().collect()
+    }
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new(EVENT_BUFFER)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(records: usize) -> NexusEvent {
+        NexusEvent::BatchCompleted { records, failed: 0 }
+    }
+
+    #[tokio::test]
+    async fn test_hub_broadcasts_and_replays() {
+        let hub = EventHub::new(3);
+        let (_, mut first) = hub.subscribe(None);
+        let (_, mut second) = hub.subscribe(None);
+
+        for records in 1..=4 {
+            hub.publish(batch(records));
+        }
+        // Every subscriber sees every event
+        assert_eq!(first.recv().await.unwrap().id, 1);
+        assert_eq!(second.recv().await.unwrap().id, 1);
+
+        // Only the last three are kept; replay starts after Last-Event-ID
+        assert_eq!(hub.recent().iter().map(|e| e.id).collect::<Vec<_>>(), vec![2, 3, 4]);
+        let (missed, _) = hub.subscribe(Some(2));
+        assert_eq!(missed.iter().map(|e| e.id).collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(hub.latest_id(), 4);
+
+        let frame = missed[0].to_sse();
+        assert!(frame.starts_with("id: 3\nevent: batch_completed\ndata: {"));
+        assert!(frame.contains(r#""type":"batch_completed""#));
+        assert!(frame.contains(r#""message":"Batch uploaded: 3 records (0 failed)""#));
+    }
+}
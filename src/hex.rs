@@ -0,0 +1,22 @@
+//! Hex encoding for wave signatures
+//!
+//! "Sixteen fingers would make this so much easier." - Trish
+
+pub fn encode(bytes: impl AsRef<[u8]>) -> String {
+    bytes.as_ref().iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn decode(s: &str) -> Result<Vec<u8>, String> {
+    if !s.is_ascii() || s.len() % 2 == 1 {
+        return Err(format!("Invalid hex string: {}", s));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16)
+                .map_err(|e| e.to_string())
+        })
+        .collect()
+}
@@ -0,0 +1,144 @@
+//! Idempotency keys for safe upload retries
+//!
+//! "Say it twice, store it once." - Trish
+//!
+//! Clients may send an `Idempotency-Key` header with an upload. The first
+//! successful response for a key is remembered and replayed verbatim for any
+//! retry of the same route, so a flaky network never creates a second copy.
+//! For a background upload the job is remembered, and a retry gets its
+//! current state instead of a second job.
+//! A hash of the query string and body is kept with it: reusing a key for a
+//! different request is a client bug and is refused rather than replayed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use actix_web::HttpRequest;
+use sha2::{Digest, Sha256};
+
+/// Request header carrying the client's idempotency key
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+/// Response header set when a stored response is replayed
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// How long a key is remembered
+pub const IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// An idempotency key together with a fingerprint of the request it was sent with
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    key: String,
+    fingerprint: [u8; 32],
+}
+
+/// The key was already used for a request with a different body
+#[derive(Debug, thiserror::Error)]
+#[error("Idempotency-Key was already used for a different request")]
+pub struct KeyReused;
+
+/// What a key replays
+#[derive(Debug, Clone, PartialEq)]
+pub enum Remembered {
+    /// The response body of an upload that ran during the request
+    Response(serde_json::Value),
+    /// The ID of the background job an `?async=true` upload was queued as
+    Job(u64),
+}
+
+struct Entry {
+    stored_at: SystemTime,
+    fingerprint: [u8; 32],
+    remembered: Remembered,
+}
+
+impl Entry {
+    fn is_fresh(&self) -> bool {
+        self.stored_at.elapsed().unwrap_or_default() < IDEMPOTENCY_TTL
+    }
+}
+
+/// Remembered upload responses, keyed by route and idempotency key
+#[derive(Default)]
+pub struct IdempotencyCache {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+pub type SharedIdempotencyCache = Arc<IdempotencyCache>;
+
+impl IdempotencyCache {
+    /// Cache key for a request, if the client sent an idempotency key.
+    /// Keys are scoped to the route so the same key can't replay across endpoints.
+    /// `body` is every part of the request body, e.g. one entry per uploaded file.
+    pub fn key_for(req: &HttpRequest, body: &[&[u8]]) -> Option<IdempotencyKey> {
+        let key = req.headers().get(IDEMPOTENCY_HEADER)?.to_str().ok()?.trim();
+        if key.is_empty() {
+            return None;
+        }
+
+        // Length-prefixed so moving bytes between parts changes the hash
+        let mut hasher = Sha256::new();
+        for part in std::iter::once(req.query_string().as_bytes()).chain(body.iter().copied()) {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+        Some(IdempotencyKey {
+            key: format!("{} {}", req.path(), key),
+            fingerprint: hasher.finalize().into(),
+        })
+    }
+
+    /// What was remembered for this key, if still fresh.
+    /// Fails if the key was stored for a different request.
+    pub fn replay(&self, key: &IdempotencyKey) -> Result<Option<Remembered>, KeyReused> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(&key.key).filter(|entry| entry.is_fresh()) {
+            Some(entry) if entry.fingerprint != key.fingerprint => Err(KeyReused),
+            Some(entry) => Ok(Some(entry.remembered.clone())),
+            None => Ok(None),
+        }
+    }
+
+    /// Remember a successful response or queued job for this key
+    pub fn remember(&self, key: IdempotencyKey, remembered: Remembered) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.is_fresh());
+        entries.insert(key.key, Entry { stored_at: SystemTime::now(), fingerprint: key.fingerprint, remembered });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_keys_are_scoped_and_replayed() {
+        let cache = IdempotencyCache::default();
+
+        let req = TestRequest::post()
+            .uri("/upload/text")
+            .insert_header((IDEMPOTENCY_HEADER, "retry-me"))
+            .to_http_request();
+        let key = IdempotencyCache::key_for(&req, &[b"hello"]).unwrap();
+        assert!(cache.replay(&key).unwrap().is_none());
+
+        let response = Remembered::Response(serde_json::json!({"wave_signature": "abc"}));
+        cache.remember(key.clone(), response.clone());
+        assert_eq!(cache.replay(&key).unwrap(), Some(response));
+
+        // Same key with a different body is refused, not replayed
+        let changed = IdempotencyCache::key_for(&req, &[b"goodbye"]).unwrap();
+        assert!(cache.replay(&changed).is_err());
+
+        // Same key on another route is a different request
+        let other = TestRequest::post()
+            .uri("/upload")
+            .insert_header((IDEMPOTENCY_HEADER, "retry-me"))
+            .to_http_request();
+        assert!(cache.replay(&IdempotencyCache::key_for(&other, &[b"hello"]).unwrap()).unwrap().is_none());
+
+        let bare = TestRequest::post().uri("/upload").to_http_request();
+        assert!(IdempotencyCache::key_for(&bare, &[b"hello"]).is_none());
+    }
+}
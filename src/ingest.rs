@@ -0,0 +1,457 @@
+//! Unified ingestion pipeline
+//!
+//! "One door in, one way through. No sneaking in the quantum window." - Trish
+//!
+//! Every upload route, the batch endpoint and any future importer hands its
+//! content to [`Ingestor::ingest`], which always runs the same steps in order:
+//! parse, consent check, dedup, MEM8 store, nexus store, events, auctioneer.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use mem8::EmotionalContext;
+
+use crate::api::{UploadParams, UploadResponse};
+use crate::events::{NexusEvent, SharedEventHub};
+use crate::auctioneer::Auctioneer;
+use crate::consent::{ConsentType, CONSENT_MANAGER};
+use crate::hex;
+use crate::m8::{M8Container, M8ContentType, M8Nexus, WaveComponent, BOUND_SIGNATURES_KEY, EXPIRES_AT_KEY, encode_wave_payload};
+use crate::markqant::Marqant;
+use crate::retention::{expiry_after, SharedRetentionPolicy};
+
+/// Contributor credited for uploads until requests carry an identity
+pub const DEFAULT_CONTRIBUTOR: &str = "quantum_uploader";
+
+/// Content handed to the pipeline
+pub enum IngestSource {
+    /// Any file; routed on its sniffed MIME type
+    File { name: String, bytes: Vec<u8> },
+    /// Plain text, never sniffed
+    Text(String),
+    /// A `.mq` file, or raw markdown to compress into one
+    Marqant { name: String, bytes: Vec<u8> },
+    /// Explicit wave parameters
+    Waves(Vec<WaveComponent>),
+    /// Memories to bind into one compound, with the containers they came from
+    Compound { memory_ids: Vec<u64>, emotion: EmotionalContext, bound_signatures: Vec<[u8; 32]> },
+}
+
+/// Per-item ingest settings that aren't query parameters
+pub struct IngestOptions {
+    pub importance: u8,
+    /// Extra header metadata; never replaces keys the container sets itself
+    pub metadata: HashMap<String, String>,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self { importance: 5, metadata: HashMap::new() }
+    }
+}
+
+/// One item to ingest
+pub struct IngestRequest {
+    pub source: IngestSource,
+    pub options: IngestOptions,
+    pub params: UploadParams,
+    pub contributor: String,
+}
+
+impl IngestRequest {
+    pub fn new(source: IngestSource, params: UploadParams) -> Self {
+        Self {
+            source,
+            options: IngestOptions::default(),
+            params,
+            contributor: DEFAULT_CONTRIBUTOR.to_string(),
+        }
+    }
+}
+
+/// Why an item wasn't ingested
+#[derive(Debug, thiserror::Error)]
+pub enum IngestError {
+    #[error("Consent required for {0:?}")]
+    ConsentRequired(ConsentType),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("An identical upload is still being stored; retry shortly")]
+    InProgress,
+    #[error("{0}")]
+    Internal(String),
+}
+
+/// What the parse step found, before anything is stored
+enum Parsed {
+    Image(Vec<u8>),
+    Audio(Vec<u8>),
+    Marqant(Marqant),
+    M8(M8Container),
+    Text { text: String, mime: Option<&'static str> },
+    Waves(Vec<WaveComponent>),
+    Compound(M8Container),
+    Blob { bytes: Vec<u8>, mime: &'static str },
+}
+
+impl Parsed {
+    /// Signature the container will have, computed without touching MEM8
+    fn signature(&self) -> Result<[u8; 32], IngestError> {
+        Ok(match self {
+            Parsed::Image(bytes) | Parsed::Audio(bytes) | Parsed::Blob { bytes, .. } => M8Container::calculate_signature(bytes),
+            Parsed::Marqant(marqant) => M8Container::calculate_signature(&marqant.to_bytes().map_err(internal)?),
+            Parsed::M8(container) | Parsed::Compound(container) => container.wave_signature,
+            Parsed::Text { text, .. } => M8Container::calculate_signature(text.as_bytes()),
+            Parsed::Waves(waves) => M8Container::calculate_signature(&encode_wave_payload(waves)),
+        })
+    }
+
+    /// Consent needed beyond the basic upload
+    fn consent(&self) -> Option<ConsentType> {
+        match self {
+            Parsed::Marqant(_) => Some(ConsentType::QuantumCompress),
+            Parsed::Image(_) | Parsed::Audio(_) | Parsed::Text { .. } | Parsed::Waves(_) => Some(ConsentType::MemoryStore),
+            Parsed::Compound(_) => Some(ConsentType::CrossSensory),
+            Parsed::M8(_) | Parsed::Blob { .. } => None,
+        }
+    }
+}
+
+/// Everything the pipeline writes to, shared by all routes
+pub struct Ingestor {
+    pub mem8: Arc<Mutex<mem8::Mem8>>,
+    pub nexus: Arc<Mutex<M8Nexus>>,
+    pub retention: SharedRetentionPolicy,
+    pub events: SharedEventHub,
+    pub auctioneer: Arc<Auctioneer>,
+}
+
+pub type SharedIngestor = Arc<Ingestor>;
+
+impl Ingestor {
+    /// Run one item through the whole pipeline
+    pub async fn ingest(&self, request: IngestRequest) -> Result<UploadResponse, IngestError> {
+        let IngestRequest { source, options, params, contributor } = request;
+        let name = match &source {
+            IngestSource::File { name, .. } | IngestSource::Marqant { name, .. } => name.clone(),
+            IngestSource::Text(_) => "text".to_string(),
+            IngestSource::Waves(_) => "waves".to_string(),
+            IngestSource::Compound { .. } => "compound".to_string(),
+        };
+
+        // Parse; decoding is CPU-bound, so it runs on the blocking pool
+        let mem8 = self.mem8.clone();
+        let mut parsed = tokio::task::spawn_blocking(move || parse(source, &mem8)).await.map_err(internal)??;
+        if let Parsed::Compound(container) = &mut parsed {
+            // A compound's emotion is part of its signature, so overrides go in before dedup
+            params.apply_emotion(container);
+            container.wave_signature = container.content_signature().map_err(internal)?;
+        }
+        let parents = crate::api::resolve_parents(&params, &self.nexus).map_err(IngestError::Invalid)?;
+
+        // Consent
+        for operation in std::iter::once(ConsentType::Upload).chain(parsed.consent()) {
+            if !CONSENT_MANAGER.check_consent(&contributor, operation.clone()) {
+                return Err(IngestError::ConsentRequired(operation));
+            }
+        }
+
+        // A TTL we can't turn into an expiry time is refused before anything is stored
+        let ttl = params.ttl.map(Duration::from_secs);
+        if let Some(ttl) = ttl {
+            expiry_after(ttl).map_err(IngestError::Invalid)?;
+        }
+
+        // Dedup: same content already stored? Don't touch MEM8 again. Otherwise
+        // claim the signature so an identical upload running alongside can't
+        // store a second copy of the memories.
+        let signature = parsed.signature()?;
+        let reservation = {
+            let mut nexus_lock = self.nexus.lock().unwrap();
+            // Same content again, maybe as a revision of something else: keep the lineage
+            let descendants = nexus_lock.descendants(&signature);
+            if let Some(parent) = parents.iter().find(|p| **p == signature || descendants.contains(p)) {
+                return Err(IngestError::Invalid(format!(
+                    "Parent {} is this content or one of its revisions", hex::encode(parent)
+                )));
+            }
+            if let Some(existing) = nexus_lock.add_parents(&signature, &parents) {
+                return Ok(duplicate_response(existing));
+            }
+            if !nexus_lock.reserve(signature) {
+                return Err(IngestError::InProgress);
+            }
+            Reservation { nexus: &self.nexus, signature }
+        };
+
+        // MEM8 store
+        let imported = matches!(parsed, Parsed::M8(_));
+        let (mem8, importance, build_name) = (self.mem8.clone(), options.importance, name.clone());
+        let (mut container, mut response) = tokio::task::spawn_blocking(move || build(parsed, &build_name, importance, mem8))
+            .await
+            .map_err(internal)??;
+
+        // Lineage carried inside an uploaded .m8 is kept unless the caller overrides it
+        if !imported || !parents.is_empty() {
+            container.header.parents = parents;
+        }
+        // Same for an expiry an uploaded .m8 already carries
+        if ttl.is_some() || container.expires_at().is_none() {
+            self.retention.lock().unwrap().apply(&mut container, ttl).map_err(IngestError::Invalid)?;
+        }
+        if !matches!(container.header.content_type, M8ContentType::Compound) {
+            params.apply_emotion(&mut container);
+        }
+        for (key, value) in &options.metadata {
+            container.header.metadata.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        response.wave_signature = hex::encode(container.wave_signature);
+        response.memory_ids = container.header.memory_ids.clone();
+
+        // Nexus store
+        let stored = container.clone();
+        if imported {
+            self.nexus.lock().unwrap().store_import(container);
+        } else {
+            self.nexus.lock().unwrap().store(container);
+        }
+        drop(reservation);
+
+        // Events
+        self.events.publish(NexusEvent::container_stored(&stored, &name));
+
+        // Auctioneer
+        self.auctioneer.process_contribution(&contributor, &stored, self.mem8.clone()).await;
+
+        Ok(response)
+    }
+}
+
+/// Build the container (storing its memories in MEM8) and the upload response.
+/// Feature extraction and compression make this CPU-bound.
+fn build(
+    parsed: Parsed,
+    name: &str,
+    importance: u8,
+    mem8: Arc<Mutex<mem8::Mem8>>,
+) -> Result<(M8Container, UploadResponse), IngestError> {
+    let (container, content_type, compression_ratio, message) = match parsed {
+        Parsed::Image(bytes) => {
+            let container = M8Container::from_image(&bytes, importance, mem8).map_err(invalid)?;
+            let dimensions = format!(
+                "{}x{}",
+                container.header.metadata["width"], container.header.metadata["height"]
+            );
+            let message = format!("Image '{}' ({}) stored as visual memory!", name, dimensions);
+            (container, "Visual".to_string(), None, message)
+        }
+        Parsed::Audio(bytes) => {
+            let container = M8Container::from_audio(&bytes, importance, mem8).map_err(invalid)?;
+            let message = format!(
+                "Audio '{}' ({}s) stored as audio memory!",
+                name, container.header.metadata["duration_secs"]
+            );
+            (container, "Audio".to_string(), None, message)
+        }
+        Parsed::Marqant(marqant) => {
+            let compression_ratio = marqant.compression_ratio();
+            let container = M8Container::from_marqant(&marqant, mem8).map_err(internal)?;
+            let message = format!("Marqant uploaded successfully! Compression ratio: {:.2}x", compression_ratio);
+            (container, "marqant".to_string(), Some(compression_ratio), message)
+        }
+        Parsed::M8(container) => {
+            let content_type = format!("{:?}", container.header.content_type);
+            (container, content_type, None, "M8 container uploaded successfully!".to_string())
+        }
+        Parsed::Text { text, mime } => {
+            let mut container = M8Container::from_text(&text, importance, mem8).map_err(internal)?;
+            if let Some(mime) = mime {
+                container.header.metadata.insert("mime".to_string(), mime.to_string());
+            }
+            let message = match mime {
+                Some(_) => format!("File '{}' uploaded as text", name),
+                None => "Text uploaded and stored in quantum memory!".to_string(),
+            };
+            (container, "text".to_string(), None, message)
+        }
+        Parsed::Waves(waves) => {
+            let container = M8Container::from_wave_patterns(&waves, importance, mem8).map_err(internal)?;
+            (container, "WavePattern".to_string(), None, "Wave pattern stored in quantum memory!".to_string())
+        }
+        Parsed::Compound(container) => {
+            let message = format!("Bound {} memories into a compound container!", container.header.memory_ids.len());
+            (container, "Compound".to_string(), None, message)
+        }
+        Parsed::Blob { bytes, mime } => {
+            let message = format!("File '{}' ({}, {} bytes) stored as a blob", name, mime, bytes.len());
+            (M8Container::from_blob(&bytes, mime, name), "Blob".to_string(), None, message)
+        }
+    };
+
+    let response = UploadResponse {
+        success: true,
+        wave_signature: String::new(),
+        content_type,
+        memory_ids: Vec::new(),
+        compression_ratio,
+        message,
+        duplicate: false,
+    };
+    Ok((container, response))
+}
+
+/// Work out what the content is, without storing anything
+fn parse(source: IngestSource, mem8: &Arc<Mutex<mem8::Mem8>>) -> Result<Parsed, IngestError> {
+    match source {
+        IngestSource::Text(text) => Ok(Parsed::Text { text, mime: None }),
+        IngestSource::Waves(waves) if waves.is_empty() => Err(IngestError::Invalid("At least one wave is required".to_string())),
+        IngestSource::Waves(waves) => Ok(Parsed::Waves(waves)),
+        IngestSource::Compound { memory_ids, emotion, bound_signatures } => {
+            // Only reads the member waves; a compound stores no new memories
+            let mut container = M8Container::from_compound(memory_ids, emotion, mem8.clone()).map_err(internal)?;
+            if !bound_signatures.is_empty() {
+                let list: Vec<String> = bound_signatures.iter().map(hex::encode).collect();
+                container.header.metadata.insert(BOUND_SIGNATURES_KEY.to_string(), list.join(","));
+            }
+            Ok(Parsed::Compound(container))
+        }
+        IngestSource::Marqant { bytes, .. } => {
+            let marqant = match Marqant::from_bytes(&bytes) {
+                Ok(mq) => mq,
+                // Try to create from raw markdown
+                Err(e) => Marqant::from_markdown(&String::from_utf8_lossy(&bytes))
+                    .map_err(|_| IngestError::Invalid(format!("Failed to parse Marqant: {}", e)))?,
+            };
+            Ok(Parsed::Marqant(marqant))
+        }
+        IngestSource::File { name, bytes } => {
+            // Route on the sniffed MIME type; the extension is only a fallback
+            let mime = crate::mime::sniff(&bytes, &name);
+            Ok(match mime {
+                "image/png" | "image/jpeg" => Parsed::Image(bytes),
+                "audio/wav" => Parsed::Audio(bytes),
+                crate::mime::MARQANT_MIME => Parsed::Marqant(Marqant::from_bytes(&bytes).map_err(invalid)?),
+                crate::mime::M8_MIME => {
+                    let container = M8Container::from_bytes(&bytes).map_err(invalid)?;
+                    // Dedup and lineage trust the signature, so it has to be the real one
+                    container.verify_signature().map_err(invalid)?;
+                    if container.header.metadata.contains_key(EXPIRES_AT_KEY) && container.expires_at().is_none() {
+                        return Err(IngestError::Invalid(format!("Invalid {} in container header", EXPIRES_AT_KEY)));
+                    }
+                    Parsed::M8(container)
+                }
+                text_mime if crate::mime::is_textual(text_mime) && crate::mime::is_text(&bytes) => {
+                    // Checked above: the bytes are valid UTF-8, so this is lossless
+                    let text = String::from_utf8_lossy(&bytes).into_owned();
+                    Parsed::Text { text, mime: Some(text_mime) }
+                }
+                // A ".txt" that isn't really text shouldn't be served back as text
+                textual if crate::mime::is_textual(textual) => Parsed::Blob { bytes, mime: crate::mime::OCTET_STREAM },
+                // Anything else is kept byte-exact as an opaque blob
+                other => Parsed::Blob { bytes, mime: other },
+            })
+        }
+    }
+}
+
+/// A signature claimed in the nexus while one upload is being built.
+/// Given back on drop, whether the upload was stored or failed.
+struct Reservation<'a> {
+    nexus: &'a Arc<Mutex<M8Nexus>>,
+    signature: [u8; 32],
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.nexus.lock().unwrap().release(&self.signature);
+    }
+}
+
+/// Upload response pointing at a container that is already stored
+fn duplicate_response(existing: &M8Container) -> UploadResponse {
+    let content_type = match existing.header.content_type {
+        M8ContentType::Marqant => "marqant".to_string(),
+        M8ContentType::Language => "text".to_string(),
+        ref other => format!("{:?}", other),
+    };
+
+    UploadResponse {
+        success: true,
+        wave_signature: hex::encode(existing.wave_signature),
+        content_type,
+        memory_ids: existing.header.memory_ids.clone(),
+        compression_ratio: existing.header.metadata.get("compression_ratio")
+            .and_then(|ratio| ratio.parse().ok()),
+        message: "Already in the nexus! Returning the existing container.".to_string(),
+        duplicate: true,
+    }
+}
+
+fn invalid(e: impl std::fmt::Display) -> IngestError {
+    IngestError::Invalid(e.to_string())
+}
+
+fn internal(e: impl std::fmt::Display) -> IngestError {
+    IngestError::Internal(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventHub;
+    use mem8::Mem8;
+    use tokio::sync::mpsc;
+
+    #[actix_web::test]
+    async fn test_ingest_dedups_and_announces() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let ingestor = Ingestor {
+            nexus: Arc::new(Mutex::new(M8Nexus::new())),
+            mem8,
+            retention: Default::default(),
+            events: Arc::new(EventHub::default()),
+            auctioneer: Arc::new(Auctioneer::new(tx)),
+        };
+
+        let text = || IngestRequest::new(IngestSource::Text("Hello, quantum world!".to_string()), UploadParams::default());
+        let first = ingestor.ingest(text()).await.unwrap();
+        let again = ingestor.ingest(text()).await.unwrap();
+        assert!(!first.duplicate);
+        assert!(again.duplicate);
+        assert_eq!(first.wave_signature, again.wave_signature);
+        assert_eq!(ingestor.events.recent().len(), 1);
+
+        // Re-uploading it as a revision records the parent on the existing container
+        let base = ingestor.ingest(IngestRequest::new(IngestSource::Text("Base".to_string()), UploadParams::default())).await.unwrap();
+        let revise = |parent: &str| IngestRequest::new(
+            IngestSource::Text("Hello, quantum world!".to_string()),
+            UploadParams { parent: Some(parent.to_string()), ..UploadParams::default() },
+        );
+        assert!(ingestor.ingest(revise(&base.wave_signature)).await.unwrap().duplicate);
+        let sig = M8Container::calculate_signature(b"Hello, quantum world!");
+        let base_sig = M8Container::calculate_signature(b"Base");
+        assert_eq!(ingestor.nexus.lock().unwrap().retrieve(&sig).unwrap().header.parents, vec![base_sig]);
+        assert_eq!(ingestor.nexus.lock().unwrap().descendants(&base_sig), vec![sig]);
+        // ...but never as a revision of itself
+        assert!(matches!(ingestor.ingest(revise(&first.wave_signature)).await, Err(IngestError::Invalid(_))));
+
+        // While another upload holds a signature, an identical one is turned away
+        let busy = M8Container::calculate_signature(b"Busy, busy");
+        assert!(ingestor.nexus.lock().unwrap().reserve(busy));
+        let busy_text = || IngestRequest::new(IngestSource::Text("Busy, busy".to_string()), UploadParams::default());
+        assert!(matches!(ingestor.ingest(busy_text()).await, Err(IngestError::InProgress)));
+        ingestor.nexus.lock().unwrap().release(&busy);
+        assert!(!ingestor.ingest(busy_text()).await.unwrap().duplicate);
+
+        let empty = IngestRequest::new(IngestSource::Waves(Vec::new()), UploadParams::default());
+        assert!(matches!(ingestor.ingest(empty).await, Err(IngestError::Invalid(_))));
+
+        // An imported .m8 whose expiry can't be represented is refused
+        let mut forever = M8Container::from_text("forever", 5, ingestor.mem8.clone()).unwrap();
+        forever.header.metadata.insert(EXPIRES_AT_KEY.to_string(), u64::MAX.to_string());
+        let bytes = forever.to_bytes().unwrap();
+        let import = IngestRequest::new(IngestSource::File { name: "forever.m8".to_string(), bytes }, UploadParams::default());
+        assert!(matches!(ingestor.ingest(import).await, Err(IngestError::Invalid(_))));
+    }
+}
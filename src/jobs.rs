@@ -0,0 +1,206 @@
+//! Background ingestion jobs
+//!
+//! "Big files deserve a waiting room, not a traffic jam." - Trish
+//!
+//! Uploads sent with `?async=true` are queued here instead of running in the
+//! request. Each job is a tokio task, and a semaphore bounds how many run at
+//! once; the CPU-bound parsing and compression inside the ingest pipeline go
+//! to the blocking pool on their own. Every status change and progress update
+//! goes out on the event stream as a `job_updated` event.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+use crate::events::{NexusEvent, SharedEventHub};
+
+/// How many jobs may run at the same time
+pub const MAX_CONCURRENT_JOBS: usize = 4;
+
+/// How long a finished job stays queryable
+pub const JOB_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Files processed so far out of the files in the job
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JobProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// A job as reported by GET /jobs/{id}
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub kind: String,
+    pub status: JobStatus,
+    pub created_at: u64, // Unix seconds
+    pub finished_at: Option<u64>,
+    pub progress: Option<JobProgress>,
+    pub result: Option<serde_json::Value>, // The upload response, once completed
+    pub error: Option<String>,
+}
+
+/// Queue of background jobs, shared by every worker
+pub struct JobQueue {
+    jobs: Mutex<HashMap<u64, Job>>,
+    next_id: AtomicU64,
+    permits: Arc<Semaphore>,
+    runtime: Handle,
+    events: SharedEventHub,
+}
+
+pub type SharedJobQueue = Arc<JobQueue>;
+
+/// Lets a running job report its progress
+#[derive(Clone)]
+pub struct JobHandle {
+    id: u64,
+    queue: SharedJobQueue,
+}
+
+impl JobHandle {
+    pub fn progress(&self, done: usize, total: usize) {
+        self.queue.update(self.id, |job| job.progress = Some(JobProgress { done, total }));
+    }
+}
+
+impl JobQueue {
+    /// Must be created inside the tokio runtime the jobs should run on
+    pub fn new(max_concurrent: usize, events: SharedEventHub) -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            runtime: Handle::current(),
+            events,
+        }
+    }
+
+    /// Queue `work` and return the job as first reported to the client.
+    /// The work gets a handle for progress updates; its output becomes the job result.
+    pub fn submit<F, Fut, E>(self: &Arc<Self>, kind: &str, work: F) -> Job
+    where
+        F: FnOnce(JobHandle) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<serde_json::Value, E>> + Send + 'static,
+        E: std::fmt::Display,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let job = Job {
+            id,
+            kind: kind.to_string(),
+            status: JobStatus::Queued,
+            created_at: unix_now(),
+            finished_at: None,
+            progress: None,
+            result: None,
+            error: None,
+        };
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            jobs.retain(|_, job| !matches!(job.finished_at, Some(at) if unix_now() >= at + JOB_TTL.as_secs()));
+            jobs.insert(id, job.clone());
+        }
+        self.events.publish(NexusEvent::JobUpdated { job: job.clone() });
+
+        let queue = self.clone();
+        self.runtime.spawn(async move {
+            // The semaphore is never closed, so this can't fail
+            let _permit = queue.permits.clone().acquire_owned().await.expect("job semaphore closed");
+            queue.update(id, |job| job.status = JobStatus::Running);
+
+            // Its own task, so a panicking job fails instead of leaving it running
+            let handle = JobHandle { id, queue: queue.clone() };
+            let outcome = tokio::spawn(async move { work(handle).await.map_err(|e| e.to_string()) })
+                .await
+                .unwrap_or_else(|e| Err(format!("Job panicked: {}", e)));
+
+            queue.finish(id, outcome);
+        });
+        job
+    }
+
+    /// Current state of a job, if it exists and hasn't been forgotten
+    pub fn get(&self, id: u64) -> Option<Job> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+
+    fn finish(&self, id: u64, outcome: Result<serde_json::Value, String>) {
+        self.update(id, |job| {
+            job.finished_at = Some(unix_now());
+            match outcome {
+                Ok(result) => {
+                    job.status = JobStatus::Completed;
+                    job.result = Some(result);
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e);
+                }
+            }
+        });
+    }
+
+    /// Change a job and announce its new state
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        let job = self.jobs.lock().unwrap().get_mut(&id).map(|job| {
+            change(job);
+            job.clone()
+        });
+        if let Some(job) = job {
+            self.events.publish(NexusEvent::JobUpdated { job });
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventHub;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_job_lifecycle() {
+        let events: SharedEventHub = Arc::new(EventHub::default());
+        let queue = Arc::new(JobQueue::new(1, events.clone()));
+
+        let ok = queue.submit("upload", |handle| async move {
+            handle.progress(1, 1);
+            Ok::<_, String>(serde_json::json!({"success": true}))
+        });
+        let failed = queue.submit("upload", |_| async { Err::<serde_json::Value, _>("broken") });
+        assert_eq!(ok.status, JobStatus::Queued);
+
+        for _ in 0..100 {
+            if queue.get(failed.id).unwrap().finished_at.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let ok = queue.get(ok.id).unwrap();
+        assert_eq!(ok.status, JobStatus::Completed);
+        assert_eq!(ok.result.unwrap()["success"], true);
+        assert_eq!(ok.progress.unwrap().done, 1);
+
+        let failed = queue.get(failed.id).unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("broken"));
+        assert!(events.recent().iter().any(|e| e.event.message() == "Job 2 failed: broken"));
+    }
+}
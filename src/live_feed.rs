@@ -0,0 +1,102 @@
+//! Fan-out of auctioneer events to /auctioneer/live sessions
+//!
+//! "A commentator with no audience is just talking to himself." - Trish
+//!
+//! The auctioneer sends its events down one mpsc channel. `run` drains it,
+//! stamps each event with a global sequence number and broadcasts it to every
+//! WebSocket session. A session that can't keep up skips the events it fell
+//! behind on (the gap shows in the sequence numbers) instead of holding up
+//! everyone else or buffering without bound.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::auctioneer::{AuctionEvent, LiveFeedMessage};
+
+/// How many events a session may fall behind before it starts skipping
+pub const SESSION_BUFFER: usize = 256;
+
+/// Broadcasts auctioneer events to every connected session
+pub struct LiveFeed {
+    sender: broadcast::Sender<Arc<LiveFeedMessage>>,
+    sequence: AtomicU64,
+}
+
+pub type SharedLiveFeed = Arc<LiveFeed>;
+
+impl LiveFeed {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender, sequence: AtomicU64::new(0) }
+    }
+
+    /// Stamp an event with the next sequence number and send it to every session
+    pub fn publish(&self, event: AuctionEvent) -> Arc<LiveFeedMessage> {
+        let message = Arc::new(LiveFeedMessage {
+            event,
+            timestamp: SystemTime::now(),
+            sequence: self.sequence.fetch_add(1, Ordering::Relaxed) + 1,
+        });
+        // No sessions connected is fine; the event is simply not shown
+        let _ = self.sender.send(message.clone());
+        message
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<LiveFeedMessage>> {
+        self.sender.subscribe()
+    }
+
+    /// Number of connected sessions
+    pub fn sessions(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new(SESSION_BUFFER)
+    }
+}
+
+/// Background task: forward everything the auctioneer says to the feed
+pub async fn run(feed: SharedLiveFeed, mut events: mpsc::UnboundedReceiver<AuctionEvent>) {
+    while let Some(event) = events.recv().await {
+        let message = feed.publish(event);
+        tracing::debug!("Auctioneer event #{} to {} sessions", message.sequence, feed.sessions());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(message: &str) -> AuctionEvent {
+        AuctionEvent::comment(message, 5)
+    }
+
+    #[tokio::test]
+    async fn test_feed_fans_out_in_sequence() {
+        let feed = Arc::new(LiveFeed::new(2));
+        let mut first = feed.subscribe();
+        let mut slow = feed.subscribe();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(comment("one")).unwrap();
+        tx.send(comment("two")).unwrap();
+        drop(tx);
+        run(feed.clone(), rx).await;
+
+        // Sessions that keep up see every event, numbered in order
+        assert_eq!(first.recv().await.unwrap().sequence, 1);
+        assert_eq!(first.recv().await.unwrap().sequence, 2);
+        feed.publish(comment("three"));
+        assert_eq!(first.recv().await.unwrap().sequence, 3);
+
+        // The slow session lost the oldest event to its buffer limit, not the others
+        assert!(matches!(slow.recv().await, Err(broadcast::error::RecvError::Lagged(1))));
+        assert_eq!(slow.recv().await.unwrap().sequence, 2);
+        assert_eq!(slow.recv().await.unwrap().sequence, 3);
+    }
+}
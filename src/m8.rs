@@ -8,21 +8,178 @@ use mem8::{Mem8, EmotionalContext};
 use crate::markqant::Marqant;
 use std::sync::{Arc, Mutex};
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::ops::Bound;
 use anyhow::Result;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::borrow::Cow;
+use std::io::{Read, Write};
 
 /// Convert EmotionalContext to 3-byte representation
-fn emotional_context_to_bytes(ec: &EmotionalContext) -> [u8; 3] {
+pub fn emotional_context_to_bytes(ec: &EmotionalContext) -> [u8; 3] {
+    // Rounded so that a neutral 0.0 lands on 128, like the neutral default
     [
-        ((ec.valence as f32 + 1.0) * 127.5) as u8,
-        ((ec.arousal as f32 + 1.0) * 127.5) as u8,
-        ((ec.dominance as f32 + 1.0) * 127.5) as u8,
+        ((ec.valence as f32 + 1.0) * 127.5).round() as u8,
+        ((ec.arousal as f32 + 1.0) * 127.5).round() as u8,
+        ((ec.dominance as f32 + 1.0) * 127.5).round() as u8,
     ]
 }
 
+/// Convert the 3-byte representation back to (valence, arousal, dominance) in [-1, 1]
+pub fn emotional_bytes_to_vad(bytes: &[u8; 3]) -> [f32; 3] {
+    bytes.map(|b| b as f32 / 127.5 - 1.0)
+}
+
+/// A wave as text: "amplitude,frequency,phase"
+fn format_wave(wave: &WaveComponent) -> String {
+    format!("{:.4},{:.4},{:.4}", wave.amplitude, wave.frequency, wave.phase)
+}
+
+/// What MEM8 is told about a wave. It only stores language, so images,
+/// sounds and waves go in as words.
+fn describe_wave(wave: &WaveComponent) -> String {
+    format!("wave (amplitude,frequency,phase) {}", format_wave(wave))
+}
+
+/// What MEM8 is told about an image or sound, from its header metadata
+fn describe_media(content_type: &M8ContentType, metadata: &HashMap<String, String>) -> String {
+    let get = |key: &str| metadata.get(key).map(String::as_str).unwrap_or("?");
+    let what = match content_type {
+        M8ContentType::Visual => format!(
+            "{}x{} image, dominant colors {}", get("width"), get("height"), get("dominant_colors"),
+        ),
+        _ => format!(
+            "{}s sound, {} Hz, {} channels, dominant frequency {} Hz",
+            get("duration_secs"), get("sample_rate"), get("channels"), get("dominant_frequency_hz"),
+        ),
+    };
+    format!("{}, wave (amplitude,frequency,phase) {}", what, get(WAVE_KEY))
+}
+
 /// Magic bytes for .m8 files
 pub const M8_MAGIC: &[u8] = b"M8C1"; // MEM8 Container v1
 
+/// Metadata key holding a container's expiry time (unix seconds)
+pub const EXPIRES_AT_KEY: &str = "expires_at";
+
+/// Metadata key holding an image's perceptual hash (16 hex digits)
+pub const PHASH_KEY: &str = "phash";
+
+/// Metadata key listing the containers a compound was bound from (comma-separated hex)
+pub const BOUND_SIGNATURES_KEY: &str = "bound_signatures";
+
+/// Metadata key holding the wave an image or sound was described to MEM8 with
+pub const WAVE_KEY: &str = "wave";
+
+/// Metadata key holding the importance the container's memories were stored with
+pub const IMPORTANCE_KEY: &str = "importance";
+
+/// Importance of memories stored from a Marqant
+const MARQANT_IMPORTANCE: u8 = 7;
+
+/// Metadata keys the nexus itself writes. Clients can read them but not edit them.
+pub const SYSTEM_METADATA_KEYS: &[&str] = &[
+    "source", "length", "mime", "filename", "size", "compression_ratio",
+    "width", "height", "dominant_colors", PHASH_KEY,
+    "duration_secs", "sample_rate", "channels", "rms", "rms_envelope", "dominant_frequency_hz",
+    WAVE_KEY, "wave_count", "memory_count", BOUND_SIGNATURES_KEY, "unbound", EXPIRES_AT_KEY, IMPORTANCE_KEY,
+];
+
+/// Magic bytes for wave-pattern payloads
+pub const WAVE_MAGIC: &[u8] = b"M8WV";
+
+/// Domain tag hashed ahead of a compound's signed fields
+const COMPOUND_SIGNATURE_TAG: &[u8] = b"M8C1/compound";
+
+/// Payloads at least this large are zlib-compressed in the data section
+pub const COMPRESSION_THRESHOLD: usize = 256;
+
+/// Largest payload a compressed data section may expand to (64 MiB)
+pub const MAX_PAYLOAD_SIZE: usize = 64 * 1024 * 1024;
+
+/// One wave in a wave-pattern payload
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WaveComponent {
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub phase: f32,
+}
+
+/// Encode the data section of WavePattern and Compound containers:
+///
+/// ```text
+/// M8WV [count:4] count × ([amplitude:4] [frequency:4] [phase:4])
+/// ```
+///
+/// All numbers are little-endian (`u32` count, `f32` wave fields). Entry `i`
+/// describes `header.memory_ids[i]`; a memory without a wave is written as a
+/// silent `0, 0, 0` entry so the two stay aligned.
+pub fn encode_wave_payload(waves: &[WaveComponent]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(WAVE_MAGIC.len() + 4 + waves.len() * 12);
+    output.extend_from_slice(WAVE_MAGIC);
+    output.extend_from_slice(&(waves.len() as u32).to_le_bytes());
+    for wave in waves {
+        output.extend_from_slice(&wave.amplitude.to_le_bytes());
+        output.extend_from_slice(&wave.frequency.to_le_bytes());
+        output.extend_from_slice(&wave.phase.to_le_bytes());
+    }
+    output
+}
+
+/// Decode a wave-pattern payload. Early compound containers stored bare
+/// 12-byte entries without magic or count; those are still accepted.
+#[allow(clippy::manual_is_multiple_of)] // usize::is_multiple_of needs Rust 1.87
+pub fn decode_wave_payload(data: &[u8]) -> Result<Vec<WaveComponent>> {
+    let entries = if data.starts_with(WAVE_MAGIC) {
+        let count_bytes = data.get(4..8)
+            .ok_or_else(|| anyhow::anyhow!("Truncated wave payload header"))?;
+        let count = u32::from_le_bytes(count_bytes.try_into()?) as usize;
+        let entries = &data[8..];
+        if entries.len() != count * 12 {
+            return Err(anyhow::anyhow!(
+                "Wave payload declares {} waves but holds {} bytes", count, entries.len()
+            ));
+        }
+        entries
+    } else if data.len() % 12 == 0 {
+        data
+    } else {
+        return Err(anyhow::anyhow!("Invalid wave payload"));
+    };
+    
+    let read_f32 = |chunk: &[u8], at: usize| f32::from_le_bytes([chunk[at], chunk[at + 1], chunk[at + 2], chunk[at + 3]]);
+    Ok(entries.chunks_exact(12)
+        .map(|chunk| WaveComponent {
+            amplitude: read_f32(chunk, 0),
+            frequency: read_f32(chunk, 4),
+            phase: read_f32(chunk, 8),
+        })
+        .collect())
+}
+
+/// The next `len` bytes after `cursor`, or an error if the file ends first
+fn take<'a>(data: &'a [u8], cursor: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let bytes = cursor.checked_add(len)
+        .and_then(|end| data.get(*cursor..end))
+        .ok_or_else(|| anyhow::anyhow!("Truncated M8 container"))?;
+    *cursor += len;
+    Ok(bytes)
+}
+
+/// Zlib-compress a payload worth compressing; `None` if it is small or doesn't shrink.
+/// Payloads over `MAX_PAYLOAD_SIZE` are stored as they are, so they can always be read back.
+fn compress_payload(plain: &[u8]) -> Option<Vec<u8>> {
+    if plain.len() < COMPRESSION_THRESHOLD || plain.len() > MAX_PAYLOAD_SIZE {
+        return None;
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(plain).ok()?;
+    let compressed = encoder.finish().ok()?;
+    (compressed.len() < plain.len()).then_some(compressed)
+}
+
 /// M8 container types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum M8ContentType {
@@ -32,8 +189,24 @@ pub enum M8ContentType {
     Visual,       // Visual memory
     Audio,        // Audio memory
     Compound,     // Multiple bound memories
+    Blob,         // Opaque binary data, kept byte-exact
 }
 
+impl M8ContentType {
+    pub const ALL: [M8ContentType; 7] = [
+        Self::Marqant, Self::WavePattern, Self::Language, Self::Visual,
+        Self::Audio, Self::Compound, Self::Blob,
+    ];
+}
+
+/// Version written into every new header. bincode has no optional fields,
+/// so any change to the header layout needs a new version and a decoder
+/// for the old one in `decode_header`.
+///
+/// - 1: the original layout; later v1 writers appended `parents`, then `compressed`
+/// - 2: today's layout, and compound signatures that cover memory IDs and emotion
+pub const M8_HEADER_VERSION: u8 = 2;
+
 /// M8 container header
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct M8Header {
@@ -42,7 +215,60 @@ pub struct M8Header {
     pub timestamp: std::time::SystemTime,
     pub memory_ids: Vec<u64>, // Associated memory IDs in MEM8
     pub emotional_context: [u8; 3], // 3-byte emotional state
-    pub metadata: HashMap<String, String>,
+    #[serde(serialize_with = "serialize_sorted")]
+    pub metadata: HashMap<String, String>, // Written in key order so .m8 bytes are reproducible
+    pub parents: Vec<[u8; 32]>, // Wave signatures of the containers this one revises
+    pub compressed: bool, // Data section is zlib-compressed
+}
+
+/// The fields every version 1 header starts with
+#[derive(Serialize, Deserialize)]
+struct M8HeaderV1 {
+    version: u8,
+    content_type: M8ContentType,
+    timestamp: std::time::SystemTime,
+    memory_ids: Vec<u64>,
+    emotional_context: [u8; 3],
+    metadata: HashMap<String, String>,
+}
+
+/// Decode a header of any supported version, upgrading old ones to the current layout
+fn decode_header(bytes: &[u8]) -> Result<M8Header> {
+    match bytes.first().copied() {
+        Some(M8_HEADER_VERSION) => bincode::deserialize(bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize header: {}", e)),
+        Some(1) => {
+            // Same encoding as bincode::serialize, but read field by field:
+            // depending on the writer, `parents` and `compressed` may be missing
+            use bincode::Options;
+            let options = bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes();
+            let invalid = |e: bincode::Error| anyhow::anyhow!("Failed to deserialize v1 header: {}", e);
+            let mut rest = bytes;
+            let v1: M8HeaderV1 = options.deserialize_from(&mut rest).map_err(invalid)?;
+            let parents = if rest.is_empty() { Vec::new() } else { options.deserialize_from(&mut rest).map_err(invalid)? };
+            let compressed = if rest.is_empty() { false } else { options.deserialize_from(&mut rest).map_err(invalid)? };
+            if !rest.is_empty() {
+                return Err(anyhow::anyhow!("Unexpected trailing bytes in v1 header"));
+            }
+            Ok(M8Header {
+                version: M8_HEADER_VERSION,
+                content_type: v1.content_type,
+                timestamp: v1.timestamp,
+                memory_ids: v1.memory_ids,
+                emotional_context: v1.emotional_context,
+                metadata: v1.metadata,
+                parents,
+                compressed,
+            })
+        }
+        Some(version) => Err(anyhow::anyhow!("Unsupported M8 header version {}", version)),
+        None => Err(anyhow::anyhow!("Empty M8 header")),
+    }
+}
+
+/// Serialize a map in key order rather than hash order
+fn serialize_sorted<S: serde::Serializer>(map: &HashMap<String, String>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    map.iter().collect::<std::collections::BTreeMap<_, _>>().serialize(serializer)
 }
 
 /// M8 container - nexus between files and wave memory
@@ -61,14 +287,14 @@ impl M8Container {
         // Store in MEM8 as language memory with semantic understanding
         let markdown = marqant.to_markdown()?;
         let mut mem8_lock = mem8.lock().unwrap();
-        let memory_id = mem8_lock.store_language(&markdown, 7).map_err(|e| anyhow::anyhow!("Failed to store language: {}", e))?; // High importance
+        let memory_id = mem8_lock.store_language(&markdown, MARQANT_IMPORTANCE).map_err(|e| anyhow::anyhow!("Failed to store language: {}", e))?;
         drop(mem8_lock);
         
-        // Create emotional context (neutral for now)
-        let emotional_context = [128u8, 128u8, 128u8]; // Neutral valence, arousal, dominance
+        // Infer emotional context from the markdown itself
+        let emotional_context = emotional_context_to_bytes(&crate::emotion::analyze(&markdown));
         
         let header = M8Header {
-            version: 1,
+            version: M8_HEADER_VERSION,
             content_type: M8ContentType::Marqant,
             timestamp: std::time::SystemTime::now(),
             memory_ids: vec![memory_id],
@@ -76,7 +302,10 @@ impl M8Container {
             metadata: HashMap::from([
                 ("source".to_string(), "marqant".to_string()),
                 ("compression_ratio".to_string(), format!("{:.2}", marqant.compression_ratio())),
+                (IMPORTANCE_KEY.to_string(), MARQANT_IMPORTANCE.to_string()),
             ]),
+            parents: Vec::new(),
+            compressed: false,
         };
         
         let wave_signature = Self::calculate_signature(&marqant_bytes);
@@ -95,10 +324,10 @@ impl M8Container {
         let memory_id = mem8_lock.store_language(text, importance).map_err(|e| anyhow::anyhow!("Failed to store language: {}", e))?;
         drop(mem8_lock);
         
-        let emotional_context = [128u8, 128u8, 128u8]; // Neutral
+        let emotional_context = emotional_context_to_bytes(&crate::emotion::analyze(text));
         
         let header = M8Header {
-            version: 1,
+            version: M8_HEADER_VERSION,
             content_type: M8ContentType::Language,
             timestamp: std::time::SystemTime::now(),
             memory_ids: vec![memory_id],
@@ -106,10 +335,52 @@ impl M8Container {
             metadata: HashMap::from([
                 ("source".to_string(), "text".to_string()),
                 ("length".to_string(), text.len().to_string()),
+                (IMPORTANCE_KEY.to_string(), importance.to_string()),
             ]),
+            parents: Vec::new(),
+            compressed: false,
         };
         
-        let data = text.as_bytes().to_vec();
+        Ok(Self::with_compressible_data(header, text.as_bytes().to_vec()))
+    }
+    
+    /// Create M8 container from a PNG/JPEG image.
+    /// The original bytes are kept losslessly; features go into the metadata.
+    pub fn from_image(bytes: &[u8], importance: u8, mem8: Arc<Mutex<Mem8>>) -> Result<Self> {
+        let features = crate::visual::analyze(bytes)?;
+        let (amplitude, frequency, phase) = features.wave_parameters();
+        
+        let dominant_colors: Vec<String> = features.dominant_colors.iter()
+            .map(crate::visual::color_hex)
+            .collect();
+        let metadata = HashMap::from([
+            ("source".to_string(), "image".to_string()),
+            ("mime".to_string(), features.mime().to_string()),
+            ("width".to_string(), features.width.to_string()),
+            ("height".to_string(), features.height.to_string()),
+            ("dominant_colors".to_string(), dominant_colors.join(",")),
+            (PHASH_KEY.to_string(), format!("{:016x}", features.phash)),
+            (WAVE_KEY.to_string(), format_wave(&WaveComponent { amplitude, frequency, phase })),
+            (IMPORTANCE_KEY.to_string(), importance.to_string()),
+        ]);
+        
+        // Register the image in MEM8 so it can be bound with other memories
+        let mut mem8_lock = mem8.lock().unwrap();
+        let memory_id = mem8_lock.store_language(&describe_media(&M8ContentType::Visual, &metadata), importance).map_err(|e| anyhow::anyhow!("Failed to store visual memory: {}", e))?;
+        drop(mem8_lock);
+        
+        let header = M8Header {
+            version: M8_HEADER_VERSION,
+            content_type: M8ContentType::Visual,
+            timestamp: std::time::SystemTime::now(),
+            memory_ids: vec![memory_id],
+            emotional_context: [128u8, 128u8, 128u8], // Neutral
+            metadata,
+            parents: Vec::new(),
+            compressed: false,
+        };
+        
+        let data = bytes.to_vec();
         let wave_signature = Self::calculate_signature(&data);
         
         Ok(M8Container {
@@ -119,26 +390,126 @@ impl M8Container {
         })
     }
     
+    /// Create M8 container from a WAV file.
+    /// The original bytes are kept losslessly; features go into the metadata.
+    pub fn from_audio(bytes: &[u8], importance: u8, mem8: Arc<Mutex<Mem8>>) -> Result<Self> {
+        let features = crate::audio::analyze(bytes)?;
+        let (amplitude, frequency, phase) = features.wave_parameters();
+        
+        let envelope: Vec<String> = features.rms_envelope.iter()
+            .map(|rms| format!("{:.4}", rms))
+            .collect();
+        let metadata = HashMap::from([
+            ("source".to_string(), "audio".to_string()),
+            ("mime".to_string(), "audio/wav".to_string()),
+            ("duration_secs".to_string(), format!("{:.3}", features.duration_secs)),
+            ("sample_rate".to_string(), features.sample_rate.to_string()),
+            ("channels".to_string(), features.channels.to_string()),
+            ("rms".to_string(), format!("{:.4}", features.rms)),
+            ("rms_envelope".to_string(), envelope.join(",")),
+            ("dominant_frequency_hz".to_string(), format!("{:.1}", features.dominant_frequency)),
+            (WAVE_KEY.to_string(), format_wave(&WaveComponent { amplitude, frequency, phase })),
+            (IMPORTANCE_KEY.to_string(), importance.to_string()),
+        ]);
+        
+        // Register the sound in MEM8, described along with the wave it actually is
+        let mut mem8_lock = mem8.lock().unwrap();
+        let memory_id = mem8_lock.store_language(&describe_media(&M8ContentType::Audio, &metadata), importance).map_err(|e| anyhow::anyhow!("Failed to store audio memory: {}", e))?;
+        drop(mem8_lock);
+        
+        let header = M8Header {
+            version: M8_HEADER_VERSION,
+            content_type: M8ContentType::Audio,
+            timestamp: std::time::SystemTime::now(),
+            memory_ids: vec![memory_id],
+            emotional_context: [128u8, 128u8, 128u8], // Neutral
+            metadata,
+            parents: Vec::new(),
+            compressed: false,
+        };
+        
+        Ok(Self::with_compressible_data(header, bytes.to_vec()))
+    }
+    
+    /// Create M8 container for binary data we can't interpret.
+    /// The bytes are kept exactly; nothing is stored in MEM8.
+    pub fn from_blob(bytes: &[u8], mime: &str, filename: &str) -> Self {
+        let header = M8Header {
+            version: M8_HEADER_VERSION,
+            content_type: M8ContentType::Blob,
+            timestamp: std::time::SystemTime::now(),
+            memory_ids: Vec::new(),
+            emotional_context: [128u8, 128u8, 128u8], // Neutral
+            metadata: HashMap::from([
+                ("source".to_string(), "blob".to_string()),
+                ("mime".to_string(), mime.to_string()),
+                ("filename".to_string(), filename.to_string()),
+                ("size".to_string(), bytes.len().to_string()),
+            ]),
+            parents: Vec::new(),
+            compressed: false,
+        };
+        
+        Self::with_compressible_data(header, bytes.to_vec())
+    }
+    
+    /// Create M8 container from explicit wave parameters.
+    /// Each wave is stored in MEM8 as its own memory.
+    pub fn from_wave_patterns(waves: &[WaveComponent], importance: u8, mem8: Arc<Mutex<Mem8>>) -> Result<Self> {
+        if waves.is_empty() {
+            return Err(anyhow::anyhow!("A wave pattern needs at least one wave"));
+        }
+        
+        let mut mem8_lock = mem8.lock().unwrap();
+        let memory_ids = waves.iter()
+            .map(|w| mem8_lock.store_language(&describe_wave(w), importance))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("Failed to store wave pattern: {}", e))?;
+        drop(mem8_lock);
+        
+        let header = M8Header {
+            version: M8_HEADER_VERSION,
+            content_type: M8ContentType::WavePattern,
+            timestamp: std::time::SystemTime::now(),
+            memory_ids,
+            emotional_context: [128u8, 128u8, 128u8], // Neutral
+            metadata: HashMap::from([
+                ("source".to_string(), "wave".to_string()),
+                ("wave_count".to_string(), waves.len().to_string()),
+                (IMPORTANCE_KEY.to_string(), importance.to_string()),
+            ]),
+            parents: Vec::new(),
+            compressed: false,
+        };
+        
+        Ok(Self::with_compressible_data(header, encode_wave_payload(waves)))
+    }
+    
     /// Create compound M8 container binding multiple memories
     pub fn from_compound(
         memory_ids: Vec<u64>, 
         emotional_context: EmotionalContext,
         mem8: Arc<Mutex<Mem8>>
     ) -> Result<Self> {
-        let mut wave_data = Vec::new();
+        let mut waves = Vec::with_capacity(memory_ids.len());
         if let Ok(mem8_lock) = mem8.lock() {
             for &id in &memory_ids {
-                if let Ok(wave) = mem8_lock.get_wave_pattern(id) {
-                    wave_data.extend_from_slice(&wave.amplitude.to_le_bytes());
-                    wave_data.extend_from_slice(&wave.frequency.to_le_bytes());
-                    wave_data.extend_from_slice(&wave.phase.to_le_bytes());
-                }
+                let wave = mem8_lock.get_wave_pattern(id)
+                    .map(|wave| WaveComponent {
+                        amplitude: wave.amplitude,
+                        frequency: wave.frequency,
+                        phase: wave.phase,
+                    })
+                    .unwrap_or(WaveComponent { amplitude: 0.0, frequency: 0.0, phase: 0.0 });
+                waves.push(wave);
             }
         }
+        let wave_data = encode_wave_payload(&waves);
         
+        let wave_signature = Self::compound_signature(&memory_ids, &emotional_context_to_bytes(&emotional_context), &wave_data);
         let memory_count = memory_ids.len();
         let header = M8Header {
-            version: 1,
+            version: M8_HEADER_VERSION,
             content_type: M8ContentType::Compound,
             timestamp: std::time::SystemTime::now(),
             memory_ids,
@@ -147,17 +518,85 @@ impl M8Container {
                 ("source".to_string(), "compound".to_string()),
                 ("memory_count".to_string(), memory_count.to_string()),
             ]),
+            parents: Vec::new(),
+            compressed: false,
         };
         
-        let wave_signature = Self::calculate_signature(&wave_data);
-        
+        // The waves alone don't identify a compound: which memories it binds
+        // and how it feels about them are part of what was signed
         Ok(M8Container {
-            header,
-            data: wave_data,
             wave_signature,
+            ..Self::with_compressible_data(header, wave_data)
         })
     }
     
+    /// Build a container whose data section may be compressed.
+    /// The wave signature is always over the plain bytes, so identical
+    /// content has one signature however it happens to be stored.
+    fn with_compressible_data(mut header: M8Header, plain: Vec<u8>) -> Self {
+        let wave_signature = Self::calculate_signature(&plain);
+        let data = match compress_payload(&plain) {
+            Some(compressed) => {
+                header.compressed = true;
+                compressed
+            }
+            None => plain,
+        };
+        
+        M8Container {
+            header,
+            data,
+            wave_signature,
+        }
+    }
+    
+    /// The plain payload, decompressing the data section if needed
+    pub fn payload(&self) -> Result<Cow<'_, [u8]>> {
+        if !self.header.compressed {
+            return Ok(Cow::Borrowed(&self.data));
+        }
+        // One byte past the limit is enough to tell a zip bomb from a big payload
+        let mut plain = Vec::new();
+        ZlibDecoder::new(&self.data[..]).take(MAX_PAYLOAD_SIZE as u64 + 1).read_to_end(&mut plain)
+            .map_err(|e| anyhow::anyhow!("Failed to decompress container data: {}", e))?;
+        if plain.len() > MAX_PAYLOAD_SIZE {
+            return Err(anyhow::anyhow!("Container data decompresses to more than {} bytes", MAX_PAYLOAD_SIZE));
+        }
+        Ok(Cow::Owned(plain))
+    }
+    
+    /// Size of the content before any compression: the markdown behind a
+    /// Marqant, the plain payload for everything else. Only reads what it
+    /// needs: the Marqant header, or the length of the decompressed stream.
+    pub fn original_len(&self) -> Result<u64> {
+        if let M8ContentType::Marqant = self.header.content_type {
+            return Ok(Marqant::read_header(&self.payload()?)?.original_size);
+        }
+        if !self.header.compressed {
+            return Ok(self.data.len() as u64);
+        }
+        let mut decoder = ZlibDecoder::new(&self.data[..]).take(MAX_PAYLOAD_SIZE as u64 + 1);
+        let len = std::io::copy(&mut decoder, &mut std::io::sink())
+            .map_err(|e| anyhow::anyhow!("Failed to decompress container data: {}", e))?;
+        if len > MAX_PAYLOAD_SIZE as u64 {
+            return Err(anyhow::anyhow!("Container data decompresses to more than {} bytes", MAX_PAYLOAD_SIZE));
+        }
+        Ok(len)
+    }
+    
+    /// The text each of this container's memories was stored in MEM8 under.
+    /// Empty for containers that only reference memories (compounds, blobs).
+    pub fn memory_texts(&self) -> Result<Vec<(u64, String)>> {
+        let texts = match self.header.content_type {
+            M8ContentType::Language => vec![String::from_utf8(self.payload()?.into_owned())?],
+            M8ContentType::Marqant => vec![Marqant::from_bytes(&self.payload()?)?.to_markdown()?],
+            M8ContentType::Visual | M8ContentType::Audio => vec![describe_media(&self.header.content_type, &self.header.metadata)],
+            M8ContentType::WavePattern => decode_wave_payload(&self.payload()?)?.iter().map(describe_wave).collect(),
+            _ => Vec::new(),
+        };
+        Ok(self.header.memory_ids.iter().copied().zip(texts).collect())
+    }
+    
     /// Serialize to .m8 format
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
         let mut output = Vec::new();
@@ -189,55 +628,128 @@ impl M8Container {
         let mut cursor = 4;
         
         // Read header
-        let header_len = u32::from_le_bytes([
-            data[cursor], data[cursor+1], data[cursor+2], data[cursor+3]
-        ]) as usize;
-        cursor += 4;
-        let header: M8Header = bincode::deserialize(&data[cursor..cursor+header_len]).map_err(|e| anyhow::anyhow!("Failed to deserialize header: {}", e))?;
-        cursor += header_len;
+        let header_len = u32::from_le_bytes(take(data, &mut cursor, 4)?.try_into()?) as usize;
+        let header_bytes = take(data, &mut cursor, header_len)?;
+        let header = decode_header(header_bytes)?;
         
         // Read wave signature
-        let mut wave_signature = [0u8; 32];
-        wave_signature.copy_from_slice(&data[cursor..cursor+32]);
-        cursor += 32;
+        let wave_signature: [u8; 32] = take(data, &mut cursor, 32)?.try_into()?;
         
         // Read data length and data
-        let data_len = u64::from_le_bytes([
-            data[cursor], data[cursor+1], data[cursor+2], data[cursor+3],
-            data[cursor+4], data[cursor+5], data[cursor+6], data[cursor+7]
-        ]) as usize;
-        cursor += 8;
+        let data_len = u64::from_le_bytes(take(data, &mut cursor, 8)?.try_into()?);
+        let data_len = usize::try_from(data_len).map_err(|_| anyhow::anyhow!("Truncated M8 container"))?;
+        let container_data = take(data, &mut cursor, data_len)?.to_vec();
         
-        let container_data = data[cursor..cursor+data_len].to_vec();
-        
-        Ok(M8Container {
+        let mut container = M8Container {
             header,
             data: container_data,
             wave_signature,
-        })
+        };
+        
+        // Version 1 compounds were signed over their waves alone. One whose
+        // signature checks out that way is re-signed under today's scheme.
+        let legacy_compound = header_bytes.first() == Some(&1)
+            && matches!(container.header.content_type, M8ContentType::Compound);
+        if legacy_compound && Self::calculate_signature(&container.payload()?) == container.wave_signature {
+            container.wave_signature = container.content_signature()?;
+        }
+        
+        Ok(container)
     }
     
-    /// Calculate SHA256 signature
-    fn calculate_signature(data: &[u8]) -> [u8; 32] {
+    /// Calculate SHA256 signature (the container's wave signature for this data).
+    /// Always computed over the plain, uncompressed payload.
+    pub fn calculate_signature(data: &[u8]) -> [u8; 32] {
         use sha2::{Sha256, Digest};
         let mut hasher = Sha256::new();
         hasher.update(data);
         hasher.finalize().into()
     }
     
+    /// Signature of a compound: its memory IDs, emotional context and wave
+    /// payload, tagged so it can never equal a plain payload signature
+    pub fn compound_signature(memory_ids: &[u64], emotional_context: &[u8; 3], wave_data: &[u8]) -> [u8; 32] {
+        use sha2::{Sha256, Digest};
+        let mut hasher = Sha256::new();
+        hasher.update(COMPOUND_SIGNATURE_TAG);
+        hasher.update((memory_ids.len() as u64).to_le_bytes());
+        for id in memory_ids {
+            hasher.update(id.to_le_bytes());
+        }
+        hasher.update(emotional_context);
+        hasher.update(wave_data);
+        hasher.finalize().into()
+    }
+    
+    /// The signature this container's content should have
+    pub fn content_signature(&self) -> Result<[u8; 32]> {
+        let payload = self.payload()?;
+        Ok(match self.header.content_type {
+            M8ContentType::Compound => Self::compound_signature(&self.header.memory_ids, &self.header.emotional_context, &payload),
+            _ => Self::calculate_signature(&payload),
+        })
+    }
+    
+    /// Check the stored wave signature against the content it claims to sign
+    pub fn verify_signature(&self) -> Result<()> {
+        if self.content_signature()? != self.wave_signature {
+            return Err(anyhow::anyhow!("Wave signature does not match the container data"));
+        }
+        Ok(())
+    }
+    
+    /// When this container expires, if it has a TTL.
+    /// An `expires_at` entry that isn't a representable time counts as none.
+    pub fn expires_at(&self) -> Option<std::time::SystemTime> {
+        self.header.metadata.get(EXPIRES_AT_KEY)
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .and_then(|secs| std::time::UNIX_EPOCH.checked_add(std::time::Duration::from_secs(secs)))
+    }
+    
     /// Extract content based on type
     pub fn extract_content(&self) -> Result<String> {
         match self.header.content_type {
             M8ContentType::Marqant => {
-                let marqant = Marqant::from_bytes(&self.data)?;
+                let marqant = Marqant::from_bytes(&self.payload()?)?;
                 marqant.to_markdown()
             }
             M8ContentType::Language => {
-                Ok(String::from_utf8_lossy(&self.data).to_string())
+                Ok(String::from_utf8_lossy(&self.payload()?).to_string())
             }
-            _ => {
-                Ok(format!("M8 Container: {:?} with {} bytes of data", 
-                    self.header.content_type, self.data.len()))
+            M8ContentType::Visual => {
+                let meta = |key: &str| self.header.metadata.get(key).map(String::as_str).unwrap_or("?");
+                Ok(format!("Image {}x{} ({}), dominant colors {}, phash {}",
+                    meta("width"), meta("height"), meta("mime"), meta("dominant_colors"), meta(PHASH_KEY)))
+            }
+            M8ContentType::Audio => {
+                let meta = |key: &str| self.header.metadata.get(key).map(String::as_str).unwrap_or("?");
+                Ok(format!("Audio {}s at {} Hz ({} channels), RMS {}, dominant frequency {} Hz",
+                    meta("duration_secs"), meta("sample_rate"), meta("channels"), meta("rms"), meta("dominant_frequency_hz")))
+            }
+            M8ContentType::Blob => {
+                let meta = |key: &str| self.header.metadata.get(key).map(String::as_str).unwrap_or("?");
+                Ok(format!("Blob '{}' ({}, {} bytes)", meta("filename"), meta("mime"), meta("size")))
+            }
+            M8ContentType::WavePattern | M8ContentType::Compound => {
+                #[derive(Serialize)]
+                struct BoundWave {
+                    memory_id: Option<u64>,
+                    #[serde(flatten)]
+                    wave: WaveComponent,
+                }
+                
+                let waves: Vec<BoundWave> = decode_wave_payload(&self.payload()?)?
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, wave)| BoundWave {
+                        memory_id: self.header.memory_ids.get(i).copied(),
+                        wave,
+                    })
+                    .collect();
+                Ok(serde_json::to_string_pretty(&serde_json::json!({
+                    "content_type": format!("{:?}", self.header.content_type),
+                    "waves": waves,
+                }))?)
             }
         }
     }
@@ -245,45 +757,577 @@ impl M8Container {
 
 /// M8 nexus - manages the quantum context storage
 pub struct M8Nexus {
-    mem8: Arc<Mutex<Mem8>>,
     containers: HashMap<[u8; 32], M8Container>, // Wave signature -> container
+    children: HashMap<[u8; 32], Vec<[u8; 32]>>, // Parent signature -> revisions
+    memory_refs: HashMap<u64, HashSet<[u8; 32]>>, // MEM8 memory ID -> containers using it
+    by_time: BTreeSet<M8Cursor>, // (timestamp in ns, signature), for listing
+    by_size: BTreeSet<M8Cursor>, // (stored bytes, signature), for listing
+    type_totals: HashMap<String, M8TypeStats>, // Kept up to date by store and remove
+    in_flight: HashSet<[u8; 32]>, // Signatures an upload is storing right now
+    imported: HashSet<[u8; 32]>, // Uploaded .m8 containers; their memory IDs aren't ours
+    memories: HashMap<u64, M8Memory>, // MEM8 memories our containers stored
+    memories_by_time: BTreeSet<(u64, u64)>, // (stored_at in ns, memory ID)
+}
+
+/// What the nexus handed MEM8 for one memory. Kept after the container that
+/// stored it is gone, since MEM8 keeps the memory too.
+#[derive(Debug, Clone)]
+pub struct M8Memory {
+    pub content: String, // The text MEM8 stored
+    pub content_type: M8ContentType, // Of the container that stored it
+    pub importance: Option<u8>, // As handed to MEM8
+    pub stored_at: std::time::SystemTime, // When that container was made
+}
+
+/// Container count and sizes for one content type
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct M8TypeStats {
+    pub containers: usize,
+    pub stored_bytes: u64, // Data sections as stored
+    pub original_bytes: u64, // Content before Marqant and zlib compression
+}
+
+/// Position in a sorted listing: the sort value and signature of the last container seen
+pub type M8Cursor = (u64, [u8; 32]);
+
+/// Orders the nexus keeps a listing index for
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum M8Sort {
+    #[default]
+    Time,
+    Size,
+}
+
+/// What to list and in which order
+#[derive(Debug, Clone, Default)]
+pub struct M8Query {
+    pub sort: M8Sort,
+    pub descending: bool,
+    pub after: Option<M8Cursor>, // Continue after this position
+    pub limit: usize,
+    pub content_type: Option<String>, // Content type name, case-insensitive
+    pub since: Option<std::time::SystemTime>,
+    pub until: Option<std::time::SystemTime>,
+    pub metadata: Vec<(String, Option<String>)>, // Key must exist, with this value if given
+    pub tag: Option<String>, // One of the comma-separated `tags`
+}
+
+impl M8Query {
+    fn matches(&self, container: &M8Container) -> bool {
+        let header = &container.header;
+        if let Some(name) = &self.content_type {
+            if !format!("{:?}", header.content_type).eq_ignore_ascii_case(name) {
+                return false;
+            }
+        }
+        if self.since.is_some_and(|since| header.timestamp < since)
+            || self.until.is_some_and(|until| header.timestamp >= until)
+        {
+            return false;
+        }
+        for (key, value) in &self.metadata {
+            match (header.metadata.get(key), value) {
+                (None, _) => return false,
+                (Some(actual), Some(wanted)) if actual != wanted => return false,
+                _ => {}
+            }
+        }
+        if let Some(tag) = &self.tag {
+            let tags = header.metadata.get("tags").map(String::as_str).unwrap_or_default();
+            if !tags.split(',').any(|t| t.trim() == tag) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// One page of a listing
+pub struct M8Page<'a> {
+    pub containers: Vec<&'a M8Container>,
+    pub next: Option<M8Cursor>, // Where the next page starts, if there is one
+}
+
+/// Would a cursor tighten this lower bound?
+fn bound_below(bound: &Bound<M8Cursor>, cursor: &M8Cursor) -> bool {
+    match bound {
+        Bound::Included(key) => key <= cursor,
+        Bound::Excluded(key) => key < cursor,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Would a cursor tighten this upper bound?
+fn bound_above(bound: &Bound<M8Cursor>, cursor: &M8Cursor) -> bool {
+    match bound {
+        Bound::Included(key) => key >= cursor,
+        Bound::Excluded(key) => key > cursor,
+        Bound::Unbounded => true,
+    }
+}
+
+/// True when no key fits between the bounds (`BTreeSet::range` panics on those)
+fn range_is_empty(lower: &Bound<M8Cursor>, upper: &Bound<M8Cursor>) -> bool {
+    match (lower, upper) {
+        (Bound::Included(low), Bound::Included(high)) => low > high,
+        (Bound::Included(low) | Bound::Excluded(low), Bound::Included(high) | Bound::Excluded(high)) => low >= high,
+        _ => false,
+    }
+}
+
+/// Outcome of removing a container from the nexus
+#[derive(Debug, Clone)]
+pub struct M8Removal {
+    pub container: M8Container,
+    pub unreferenced_memory_ids: Vec<u64>, // No container references these any more; MEM8 keeps them
+    pub flagged_compounds: Vec<[u8; 32]>, // Compounds that were bound to the removed container
+}
+
+/// Version history of a container: every ancestor and descendant reachable
+/// from it, plus the child -> parent edges between them
+#[derive(Debug, Clone)]
+pub struct M8History {
+    pub nodes: Vec<[u8; 32]>,
+    pub edges: Vec<([u8; 32], [u8; 32])>,
 }
 
 impl M8Nexus {
-    pub fn new(mem8: Arc<Mutex<Mem8>>) -> Self {
+    pub fn new() -> Self {
         Self {
-            mem8,
             containers: HashMap::new(),
+            children: HashMap::new(),
+            memory_refs: HashMap::new(),
+            by_time: BTreeSet::new(),
+            by_size: BTreeSet::new(),
+            type_totals: HashMap::new(),
+            in_flight: HashSet::new(),
+            imported: HashSet::new(),
+            memories: HashMap::new(),
+            memories_by_time: BTreeSet::new(),
         }
     }
     
-    /// Store a container and return its wave signature
+    /// Claim a signature for an upload that is about to write to MEM8.
+    /// False if it is already stored or another upload holds it; `release` gives it back.
+    pub fn reserve(&mut self, signature: [u8; 32]) -> bool {
+        !self.containers.contains_key(&signature) && self.in_flight.insert(signature)
+    }
+    
+    /// Give back a signature claimed by `reserve`
+    pub fn release(&mut self, signature: &[u8; 32]) {
+        self.in_flight.remove(signature);
+    }
+    
+    /// Store a container and return its wave signature.
+    /// A container already stored under the same signature is kept untouched.
     pub fn store(&mut self, container: M8Container) -> [u8; 32] {
+        self.insert(container, false)
+    }
+    
+    /// Store a container uploaded as a `.m8` file. Its memory IDs name memories
+    /// in the MEM8 of whichever instance wrote it, so they don't count as
+    /// references to ours.
+    pub fn store_import(&mut self, container: M8Container) -> [u8; 32] {
+        self.insert(container, true)
+    }
+    
+    fn insert(&mut self, container: M8Container, imported: bool) -> [u8; 32] {
         let signature = container.wave_signature;
+        if self.containers.contains_key(&signature) {
+            return signature;
+        }
+        if imported {
+            self.imported.insert(signature);
+        } else {
+            self.record_memories(&container);
+        }
+        for parent in &container.header.parents {
+            let revisions = self.children.entry(*parent).or_default();
+            if !revisions.contains(&signature) {
+                revisions.push(signature);
+            }
+        }
+        for &memory_id in container.header.memory_ids.iter().filter(|_| !imported) {
+            self.memory_refs.entry(memory_id).or_default().insert(signature);
+        }
+        let (time, size) = Self::sort_values(&container);
+        self.by_time.insert((time, signature));
+        self.by_size.insert((size, signature));
+        let totals = self.type_totals.entry(format!("{:?}", container.header.content_type)).or_default();
+        totals.containers += 1;
+        totals.stored_bytes += size;
+        totals.original_bytes += container.original_len().unwrap_or(size);
         self.containers.insert(signature, container);
         signature
     }
     
+    /// Remove a container, reporting the MEM8 memories no other container
+    /// references any more. MEM8 itself keeps every memory.
+    ///
+    /// Compound containers still bound to one of its memories are flagged with an
+    /// `unbound` metadata entry listing the removed signatures. Lineage edges are
+    /// kept so history traversal still passes through the removed revision.
+    pub fn remove(&mut self, signature: &[u8; 32]) -> Option<M8Removal> {
+        let container = self.containers.remove(signature)?;
+        let (time, size) = Self::sort_values(&container);
+        self.by_time.remove(&(time, *signature));
+        self.by_size.remove(&(size, *signature));
+        let type_name = format!("{:?}", container.header.content_type);
+        if let Some(totals) = self.type_totals.get_mut(&type_name) {
+            totals.containers -= 1;
+            totals.stored_bytes = totals.stored_bytes.saturating_sub(size);
+            totals.original_bytes = totals.original_bytes.saturating_sub(container.original_len().unwrap_or(size));
+            if totals.containers == 0 {
+                self.type_totals.remove(&type_name);
+            }
+        }
+        let mut unreferenced_memory_ids = Vec::new();
+        let mut flagged_compounds = Vec::new();
+        let memory_ids = match self.imported.remove(signature) {
+            true => &[][..],
+            false => &container.header.memory_ids[..],
+        };
+        
+        for &memory_id in memory_ids {
+            let Some(users) = self.memory_refs.get_mut(&memory_id) else {
+                continue;
+            };
+            users.remove(signature);
+            
+            if users.is_empty() {
+                self.memory_refs.remove(&memory_id);
+                unreferenced_memory_ids.push(memory_id);
+                continue;
+            }
+            
+            for user in users.iter() {
+                let Some(compound) = self.containers.get_mut(user) else {
+                    continue;
+                };
+                if !matches!(compound.header.content_type, M8ContentType::Compound)
+                    || flagged_compounds.contains(user)
+                {
+                    continue;
+                }
+                let removed = crate::hex::encode(signature);
+                let unbound = compound.header.metadata.entry("unbound".to_string()).or_default();
+                if !unbound.split(',').any(|s| s == removed) {
+                    if !unbound.is_empty() {
+                        unbound.push(',');
+                    }
+                    unbound.push_str(&removed);
+                }
+                flagged_compounds.push(*user);
+            }
+        }
+        
+        Some(M8Removal {
+            container,
+            unreferenced_memory_ids,
+            flagged_compounds,
+        })
+    }
+    
+    /// Signatures of containers whose TTL has elapsed
+    pub fn expired(&self, now: std::time::SystemTime) -> Vec<[u8; 32]> {
+        self.containers.iter()
+            .filter(|(_, c)| c.expires_at().is_some_and(|at| at <= now))
+            .map(|(sig, _)| *sig)
+            .collect()
+    }
+    
+    /// Every container with a TTL, soonest expiry first
+    pub fn expiring(&self) -> Vec<([u8; 32], M8ContentType, std::time::SystemTime)> {
+        let mut expiring: Vec<_> = self.containers.iter()
+            .filter_map(|(sig, c)| {
+                c.expires_at().map(|at| (*sig, c.header.content_type.clone(), at))
+            })
+            .collect();
+        expiring.sort_by_key(|(_, _, at)| *at);
+        expiring
+    }
+    
+    /// Member containers of a compound: the containers it was bound from that are
+    /// still stored, plus any other non-compound container sharing one of its memories
+    pub fn compound_members(&self, signature: &[u8; 32]) -> Vec<[u8; 32]> {
+        let Some(compound) = self.containers.get(signature) else {
+            return Vec::new();
+        };
+        
+        let bound = compound.header.metadata.get(BOUND_SIGNATURES_KEY)
+            .map(|list| {
+                list.split(',')
+                    .filter_map(|hex_sig| crate::hex::decode(hex_sig).ok())
+                    .filter_map(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let sharing: Vec<_> = compound.header.memory_ids.iter()
+            .flat_map(|id| self.containers_for_memory(*id))
+            .filter(|sig| {
+                self.containers.get(sig)
+                    .is_some_and(|c| !matches!(c.header.content_type, M8ContentType::Compound))
+            })
+            .collect();
+        
+        let mut members = Vec::new();
+        for sig in bound.into_iter().chain(sharing) {
+            if sig != *signature && self.containers.contains_key(&sig) && !members.contains(&sig) {
+                members.push(sig);
+            }
+        }
+        members
+    }
+    
+    /// Visual containers whose perceptual hash is within `max_distance` bits, closest first
+    pub fn similar_images(&self, phash: u64, max_distance: u32) -> Vec<([u8; 32], u32)> {
+        let mut matches: Vec<_> = self.containers.iter()
+            .filter(|(_, c)| matches!(c.header.content_type, M8ContentType::Visual))
+            .filter_map(|(sig, c)| {
+                let other = u64::from_str_radix(c.header.metadata.get(PHASH_KEY)?, 16).ok()?;
+                let distance = crate::visual::hamming_distance(phash, other);
+                (distance <= max_distance).then_some((*sig, distance))
+            })
+            .collect();
+        matches.sort_by_key(|(sig, distance)| (*distance, *sig));
+        matches
+    }
+    
+    /// Remember what a container's memories were stored in MEM8 as.
+    /// The first container to store a memory describes it.
+    fn record_memories(&mut self, container: &M8Container) {
+        let texts = match container.memory_texts() {
+            Ok(texts) => texts,
+            Err(e) => {
+                tracing::warn!("Failed to read the memories of {}: {}", crate::hex::encode(container.wave_signature), e);
+                return;
+            }
+        };
+        let importance = container.header.metadata.get(IMPORTANCE_KEY).and_then(|i| i.parse().ok());
+        let stored_at = container.header.timestamp;
+        for (memory_id, content) in texts {
+            if self.memories.contains_key(&memory_id) {
+                continue;
+            }
+            self.memories_by_time.insert((Self::nanos(stored_at), memory_id));
+            self.memories.insert(memory_id, M8Memory {
+                content,
+                content_type: container.header.content_type.clone(),
+                importance,
+                stored_at,
+            });
+        }
+    }
+    
+    /// A MEM8 memory one of our containers stored
+    pub fn memory(&self, memory_id: u64) -> Option<&M8Memory> {
+        self.memories.get(&memory_id)
+    }
+    
+    /// Memories stored from `since` up to and including `until`, oldest first
+    pub fn memories_between(
+        &self,
+        since: std::time::SystemTime,
+        until: Option<std::time::SystemTime>,
+    ) -> impl Iterator<Item = (u64, &M8Memory)> + '_ {
+        let lower = Bound::Included((Self::nanos(since), 0));
+        let upper = match until {
+            Some(until) => Bound::Included((Self::nanos(until), u64::MAX)),
+            None => Bound::Unbounded,
+        };
+        let range = match (lower, upper) {
+            (Bound::Included(low), Bound::Included(high)) if low > high => None,
+            bounds => Some(self.memories_by_time.range(bounds)),
+        };
+        range.into_iter().flatten().map(|&(_, id)| (id, &self.memories[&id]))
+    }
+    
+    /// The most recently stored language memory: text or a Marqant's markdown
+    pub fn latest_language_memory(&self) -> Option<(u64, &M8Memory)> {
+        self.memories_by_time.iter()
+            .rev()
+            .map(|&(_, id)| (id, &self.memories[&id]))
+            .find(|(_, memory)| matches!(memory.content_type, M8ContentType::Language | M8ContentType::Marqant))
+    }
+    
+    /// Containers currently referencing a MEM8 memory
+    pub fn containers_for_memory(&self, memory_id: u64) -> Vec<[u8; 32]> {
+        self.memory_refs.get(&memory_id)
+            .map(|users| users.iter().copied().collect())
+            .unwrap_or_default()
+    }
+    
+    /// Apply metadata edits to a stored container: `Some` sets a key, `None`
+    /// removes it. Only the header changes, so the wave signature stays the same.
+    pub fn update_metadata(
+        &mut self,
+        signature: &[u8; 32],
+        changes: HashMap<String, Option<String>>,
+    ) -> Option<&M8Container> {
+        let container = self.containers.get_mut(signature)?;
+        for (key, value) in changes {
+            match value {
+                Some(value) => container.header.metadata.insert(key, value),
+                None => container.header.metadata.remove(&key),
+            };
+        }
+        Some(container)
+    }
+    
+    /// Record more parents on a stored container, e.g. when identical content
+    /// is uploaded again as a revision of something else. Returns the container
+    /// once updated, or `None` if it isn't stored. Callers rule out cycles.
+    pub fn add_parents(&mut self, signature: &[u8; 32], parents: &[[u8; 32]]) -> Option<&M8Container> {
+        let container = self.containers.get_mut(signature)?;
+        for parent in parents {
+            if container.header.parents.contains(parent) {
+                continue;
+            }
+            container.header.parents.push(*parent);
+            self.children.entry(*parent).or_default().push(*signature);
+        }
+        Some(container)
+    }
+    
     /// Retrieve a container by wave signature
     pub fn retrieve(&self, signature: &[u8; 32]) -> Option<&M8Container> {
         self.containers.get(signature)
     }
     
-    /// List all containers with metadata
-    pub fn list(&self) -> Vec<([u8; 32], M8ContentType, std::time::SystemTime)> {
-        self.containers.iter()
-            .map(|(sig, container)| (*sig, container.header.content_type.clone(), container.header.timestamp))
-            .collect()
+    /// One page of containers in the query's order, read from the sort index so
+    /// the cost is the page plus whatever the filters skip, not the whole nexus
+    pub fn page(&self, query: &M8Query) -> M8Page<'_> {
+        let index = match query.sort {
+            M8Sort::Time => &self.by_time,
+            M8Sort::Size => &self.by_size,
+        };
+        
+        // A time-sorted listing only has to look inside the time window
+        let (mut lower, mut upper) = (Bound::Unbounded, Bound::Unbounded);
+        if query.sort == M8Sort::Time {
+            if let Some(since) = query.since {
+                lower = Bound::Included((Self::nanos(since), [0; 32]));
+            }
+            if let Some(until) = query.until {
+                upper = Bound::Excluded((Self::nanos(until), [0; 32]));
+            }
+        }
+        match (query.after, query.descending) {
+            (Some(after), false) if bound_below(&lower, &after) => lower = Bound::Excluded(after),
+            (Some(after), true) if bound_above(&upper, &after) => upper = Bound::Excluded(after),
+            _ => {}
+        }
+        if range_is_empty(&lower, &upper) {
+            return M8Page { containers: Vec::new(), next: None };
+        }
+        
+        let range = index.range((lower, upper));
+        let mut keys: Box<dyn Iterator<Item = &M8Cursor>> = if query.descending {
+            Box::new(range.rev())
+        } else {
+            Box::new(range)
+        };
+        let mut matching = keys.by_ref()
+            .filter_map(|key| Some((*key, self.containers.get(&key.1)?)))
+            .filter(|(_, container)| query.matches(container));
+        
+        let mut containers = Vec::new();
+        let mut last = None;
+        for (key, container) in matching.by_ref().take(query.limit) {
+            containers.push(container);
+            last = Some(key);
+        }
+        // Only hand out a cursor when another match actually follows
+        let next = last.filter(|_| matching.next().is_some());
+        M8Page { containers, next }
+    }
+    
+    /// Sort index values of a container: (timestamp in ns, stored bytes)
+    fn sort_values(container: &M8Container) -> (u64, u64) {
+        (Self::nanos(container.header.timestamp), container.data.len() as u64)
+    }
+    
+    fn nanos(time: std::time::SystemTime) -> u64 {
+        time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos() as u64
+    }
+    
+    /// Walk parent pointers breadth-first, nearest ancestors first
+    pub fn ancestors(&self, signature: &[u8; 32]) -> Vec<[u8; 32]> {
+        self.walk(signature, |sig| {
+            self.containers.get(sig)
+                .map(|c| c.header.parents.clone())
+                .unwrap_or_default()
+        })
+    }
+    
+    /// Walk revisions breadth-first, direct children first
+    pub fn descendants(&self, signature: &[u8; 32]) -> Vec<[u8; 32]> {
+        self.walk(signature, |sig| {
+            self.children.get(sig).cloned().unwrap_or_default()
+        })
+    }
+    
+    /// Full lineage DAG around a container (ancestors, itself, descendants)
+    pub fn history(&self, signature: &[u8; 32]) -> M8History {
+        let mut nodes = self.ancestors(signature);
+        nodes.reverse();
+        nodes.push(*signature);
+        nodes.extend(self.descendants(signature));
+        
+        let members: HashSet<[u8; 32]> = nodes.iter().copied().collect();
+        let edges = nodes.iter()
+            .filter_map(|sig| self.containers.get(sig).map(|c| (sig, c)))
+            .flat_map(|(sig, c)| {
+                c.header.parents.iter()
+                    .filter(|p| members.contains(*p))
+                    .map(move |p| (*sig, *p))
+            })
+            .collect();
+        
+        M8History { nodes, edges }
+    }
+    
+    /// Breadth-first traversal shared by ancestors/descendants.
+    /// Signatures that are no longer stored are still reported, but not expanded.
+    fn walk<F>(&self, start: &[u8; 32], next: F) -> Vec<[u8; 32]>
+    where
+        F: Fn(&[u8; 32]) -> Vec<[u8; 32]>,
+    {
+        let mut seen = HashSet::from([*start]);
+        let mut queue = VecDeque::from([*start]);
+        let mut found = Vec::new();
+        
+        while let Some(current) = queue.pop_front() {
+            for sig in next(&current) {
+                if seen.insert(sig) {
+                    found.push(sig);
+                    queue.push_back(sig);
+                }
+            }
+        }
+        found
+    }
+    
+    /// Container count and byte totals per content type
+    pub fn type_stats(&self) -> HashMap<String, M8TypeStats> {
+        self.type_totals.clone()
+    }
+    
+    /// Number of stored containers
+    pub fn container_count(&self) -> usize {
+        self.containers.len()
+    }
+    
+    /// Number of distinct MEM8 memories referenced by stored containers
+    pub fn referenced_memories(&self) -> usize {
+        self.memory_refs.len()
     }
     
     /// Get nexus statistics
     pub fn stats(&self) -> HashMap<String, usize> {
-        let mut counts = HashMap::new();
-        for container in self.containers.values() {
-            let type_name = format!("{:?}", container.header.content_type);
-            *counts.entry(type_name).or_insert(0) += 1;
-        }
-        counts
+        self.type_totals.iter()
+            .map(|(type_name, totals)| (type_name.clone(), totals.containers))
+            .collect()
     }
 }
 
@@ -301,5 +1345,365 @@ mod tests {
         
         assert_eq!(container.wave_signature, deserialized.wave_signature);
         assert_eq!(container.extract_content().unwrap(), "Hello, quantum world!");
+        // Downloads re-upload byte-for-byte
+        assert_eq!(deserialized.to_bytes().unwrap(), serialized);
+        assert_eq!(deserialized.header.metadata[IMPORTANCE_KEY], "5");
+        
+        // A forged signature is caught
+        assert!(deserialized.verify_signature().is_ok());
+        let mut forged = deserialized;
+        forged.wave_signature[0] ^= 0xff;
+        assert!(forged.verify_signature().is_err());
+    }
+    
+    #[test]
+    fn test_truncated_m8_is_rejected() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let bytes = M8Container::from_text("cut short", 5, mem8).unwrap().to_bytes().unwrap();
+        
+        assert!(M8Container::from_bytes(b"M8C1").is_err());
+        for len in 4..bytes.len() {
+            assert!(M8Container::from_bytes(&bytes[..len]).is_err(), "prefix of {} bytes", len);
+        }
+        
+        // A data length past the end of the file
+        let mut oversized = bytes.clone();
+        let len_at = bytes.len() - "cut short".len() - 8;
+        oversized[len_at..len_at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(M8Container::from_bytes(&oversized).is_err());
+    }
+    
+    #[test]
+    fn test_v1_headers_still_decode() {
+        let v1 = M8HeaderV1 {
+            version: 1,
+            content_type: M8ContentType::Language,
+            timestamp: std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000),
+            memory_ids: vec![3],
+            emotional_context: [128, 128, 128],
+            metadata: HashMap::from([("source".to_string(), "text".to_string())]),
+        };
+        let text = b"written before lineage existed";
+        // .m8 bytes as a v1 writer produced them, signed over the plain payload
+        let fixture = |header: Vec<u8>, payload: &[u8]| {
+            let mut bytes = M8_MAGIC.to_vec();
+            bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&header);
+            bytes.extend_from_slice(&M8Container::calculate_signature(payload));
+            bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(payload);
+            bytes
+        };
+        
+        // The original layout, then with `parents`, then with `compressed` too
+        let original = bincode::serialize(&v1).unwrap();
+        let mut with_parents = original.clone();
+        with_parents.extend(bincode::serialize(&vec![[9u8; 32]]).unwrap());
+        let mut with_compressed = with_parents.clone();
+        with_compressed.extend(bincode::serialize(&false).unwrap());
+        
+        for (header, parents) in [(original, 0), (with_parents, 1), (with_compressed, 1)] {
+            let container = M8Container::from_bytes(&fixture(header, text)).unwrap();
+            assert_eq!(container.header.version, M8_HEADER_VERSION);
+            assert_eq!(container.header.memory_ids, vec![3]);
+            assert_eq!(container.header.parents.len(), parents);
+            assert!(!container.header.compressed);
+            assert_eq!(container.extract_content().unwrap(), "written before lineage existed");
+            assert!(container.verify_signature().is_ok());
+            
+            // Saved again, it is a current-version file
+            let upgraded = M8Container::from_bytes(&container.to_bytes().unwrap()).unwrap();
+            assert_eq!(upgraded.header.parents, container.header.parents);
+        }
+        
+        let mut unknown = bincode::serialize(&v1).unwrap();
+        unknown[0] = 99;
+        assert!(M8Container::from_bytes(&fixture(unknown, text)).is_err());
+        
+        // A v1 compound, signed over its waves alone, is re-signed on load
+        let waves = encode_wave_payload(&[WaveComponent { amplitude: 1.0, frequency: 3.0, phase: 0.0 }]);
+        let compound = M8HeaderV1 { content_type: M8ContentType::Compound, ..v1 };
+        let container = M8Container::from_bytes(&fixture(bincode::serialize(&compound).unwrap(), &waves)).unwrap();
+        assert_eq!(container.wave_signature, M8Container::compound_signature(&[3], &[128, 128, 128], &waves));
+        assert!(container.verify_signature().is_ok());
+    }
+    
+    #[test]
+    fn test_large_text_is_compressed() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let text = "The quantum field remembers. ".repeat(100);
+        let container = M8Container::from_text(&text, 5, mem8.clone()).unwrap();
+        
+        assert!(container.header.compressed);
+        assert!(container.data.len() < text.len());
+        assert_eq!(container.wave_signature, M8Container::calculate_signature(text.as_bytes()));
+        
+        let deserialized = M8Container::from_bytes(&container.to_bytes().unwrap()).unwrap();
+        assert_eq!(deserialized.extract_content().unwrap(), text);
+        
+        // Short text isn't worth compressing
+        assert!(!M8Container::from_text("tiny", 5, mem8).unwrap().header.compressed);
+        
+        // A data section that inflates past the limit is refused, not expanded
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&vec![0u8; MAX_PAYLOAD_SIZE + 1]).unwrap();
+        let bomb = M8Container { data: encoder.finish().unwrap(), ..container };
+        assert!(bomb.payload().is_err());
+        assert!(bomb.verify_signature().is_err());
+    }
+    
+    #[test]
+    fn test_wave_payload_roundtrip() {
+        let waves = vec![
+            WaveComponent { amplitude: 0.8, frequency: 440.0, phase: 0.5 },
+            WaveComponent { amplitude: 0.2, frequency: 7.83, phase: -1.0 },
+        ];
+        let payload = encode_wave_payload(&waves);
+        assert_eq!(&payload[0..4], WAVE_MAGIC);
+        assert_eq!(payload.len(), 8 + 2 * 12);
+        assert_eq!(decode_wave_payload(&payload).unwrap(), waves);
+        
+        // Legacy bare entries still decode
+        assert_eq!(decode_wave_payload(&payload[8..]).unwrap(), waves);
+        assert!(decode_wave_payload(&payload[..payload.len() - 1]).is_err());
+        
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let container = M8Container::from_wave_patterns(&waves, 5, mem8).unwrap();
+        assert_eq!(container.header.memory_ids.len(), 2);
+        assert_eq!(describe_wave(&waves[0]), "wave (amplitude,frequency,phase) 0.8000,440.0000,0.5000");
+        let json: serde_json::Value = serde_json::from_str(&container.extract_content().unwrap()).unwrap();
+        assert_eq!(json["waves"].as_array().unwrap().len(), 2);
+        assert_eq!(json["waves"][0]["frequency"], 440.0);
+    }
+    
+    #[test]
+    fn test_nexus_lineage() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        
+        let v1 = nexus.store(M8Container::from_text("draft", 5, mem8.clone()).unwrap());
+        let mut second = M8Container::from_text("draft, revised", 5, mem8.clone()).unwrap();
+        second.header.parents = vec![v1];
+        let v2 = nexus.store(second);
+        let mut third = M8Container::from_text("draft, revised again", 5, mem8).unwrap();
+        third.header.parents = vec![v2];
+        let v3 = nexus.store(third);
+        
+        assert_eq!(nexus.ancestors(&v3), vec![v2, v1]);
+        assert_eq!(nexus.descendants(&v1), vec![v2, v3]);
+        
+        let history = nexus.history(&v2);
+        assert_eq!(history.nodes, vec![v1, v2, v3]);
+        assert_eq!(history.edges.len(), 2);
+    }
+    
+    #[test]
+    fn test_nexus_remove_reference_counting() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        
+        let mut text = M8Container::from_text("shared thought", 5, mem8.clone()).unwrap();
+        text.header.memory_ids = vec![42];
+        let text_sig = nexus.store(text);
+        
+        let emotion = EmotionalContext { valence: 0.0, arousal: 0.0, dominance: 0.0 };
+        let compound = M8Container::from_compound(vec![42], emotion, mem8.clone()).unwrap();
+        let compound_sig = nexus.store(compound);
+        
+        // The compound still binds memory 42, so it stays referenced
+        let removal = nexus.remove(&text_sig).unwrap();
+        assert!(removal.unreferenced_memory_ids.is_empty());
+        assert_eq!(removal.flagged_compounds, vec![compound_sig]);
+        assert!(nexus.retrieve(&compound_sig).unwrap().header.metadata.contains_key("unbound"));
+        
+        let removal = nexus.remove(&compound_sig).unwrap();
+        assert_eq!(removal.unreferenced_memory_ids, vec![42]);
+        assert!(nexus.containers_for_memory(42).is_empty());
+        assert!(nexus.remove(&compound_sig).is_none());
+        
+        // An imported container's memory IDs belong to another MEM8
+        let mut imported = M8Container::from_text("from elsewhere", 5, mem8.clone()).unwrap();
+        imported.header.memory_ids = vec![42];
+        let calm = EmotionalContext { valence: 0.2, arousal: 0.0, dominance: 0.0 };
+        let local = nexus.store(M8Container::from_compound(vec![42], calm, mem8).unwrap());
+        let imported_sig = nexus.store_import(imported);
+        assert_eq!(nexus.containers_for_memory(42), vec![local]);
+        let removal = nexus.remove(&imported_sig).unwrap();
+        assert!(removal.unreferenced_memory_ids.is_empty() && removal.flagged_compounds.is_empty());
+        assert_eq!(nexus.containers_for_memory(42), vec![local]);
+    }
+    
+    #[test]
+    fn test_compound_members() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        
+        let mut text = M8Container::from_text("a thought", 5, mem8.clone()).unwrap();
+        text.header.memory_ids = vec![7];
+        let text_sig = nexus.store(text);
+        let mut other = M8Container::from_text("another thought", 5, mem8.clone()).unwrap();
+        other.header.memory_ids = vec![8];
+        let other_sig = nexus.store(other);
+        
+        let emotion = EmotionalContext { valence: 0.5, arousal: 0.0, dominance: 0.0 };
+        let mut compound = M8Container::from_compound(vec![7, 8], emotion, mem8).unwrap();
+        compound.header.metadata.insert(BOUND_SIGNATURES_KEY.to_string(), crate::hex::encode(text_sig));
+        let compound_sig = nexus.store(compound);
+        
+        assert_eq!(nexus.compound_members(&compound_sig), vec![text_sig, other_sig]);
+    }
+    
+    #[test]
+    fn test_compound_signature_covers_binding() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let waves = M8Container::from_wave_patterns(&[WaveComponent { amplitude: 0.5, frequency: 2.0, phase: 0.0 }], 5, mem8.clone()).unwrap();
+        let memory_ids = waves.header.memory_ids.clone();
+        
+        let calm = EmotionalContext { valence: 0.0, arousal: -0.5, dominance: 0.0 };
+        let angry = EmotionalContext { valence: -0.8, arousal: 0.9, dominance: 0.5 };
+        let compound = M8Container::from_compound(memory_ids.clone(), calm, mem8.clone()).unwrap();
+        
+        // Same payload as a wave container holding the same waves, yet a different signature
+        let member_waves = decode_wave_payload(&compound.payload().unwrap()).unwrap();
+        let same_waves = M8Container::from_wave_patterns(&member_waves, 5, mem8.clone()).unwrap();
+        assert_eq!(compound.payload().unwrap(), same_waves.payload().unwrap());
+        assert_ne!(compound.wave_signature, same_waves.wave_signature);
+        assert!(compound.verify_signature().is_ok());
+        
+        // Another emotion or another set of memories is another compound
+        let other = M8Container::from_compound(memory_ids.clone(), angry, mem8.clone()).unwrap();
+        assert_ne!(compound.wave_signature, other.wave_signature);
+        let more = M8Container::from_compound(vec![memory_ids[0], 999], calm, mem8).unwrap();
+        assert_ne!(compound.wave_signature, more.wave_signature);
+    }
+    
+    #[test]
+    fn test_nexus_store_keeps_existing() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        
+        let mut first = M8Container::from_text("same words", 5, mem8.clone()).unwrap();
+        first.header.metadata.insert("origin".to_string(), "first".to_string());
+        let sig = nexus.store(first);
+        
+        let second = M8Container::from_text("same words", 5, mem8).unwrap();
+        assert_eq!(nexus.store(second), sig);
+        assert_eq!(nexus.retrieve(&sig).unwrap().header.metadata["origin"], "first");
+        
+        // Only one upload at a time may claim a signature, and never a stored one
+        let other = M8Container::calculate_signature(b"other words");
+        assert!(!nexus.reserve(sig));
+        assert!(nexus.reserve(other));
+        assert!(!nexus.reserve(other));
+        nexus.release(&other);
+        assert!(nexus.reserve(other));
+    }
+    
+    #[test]
+    fn test_nexus_update_metadata() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        let sig = nexus.store(M8Container::from_text("editable", 5, mem8).unwrap());
+        
+        let changes = HashMap::from([
+            ("title".to_string(), Some("Notes".to_string())),
+            ("source".to_string(), None),
+        ]);
+        let updated = nexus.update_metadata(&sig, changes).unwrap();
+        assert_eq!(updated.wave_signature, sig);
+        assert_eq!(updated.header.metadata["title"], "Notes");
+        assert!(!updated.header.metadata.contains_key("source"));
+        assert!(nexus.update_metadata(&[0; 32], HashMap::new()).is_none());
+    }
+    
+    #[test]
+    fn test_nexus_page() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        for i in 0..5 {
+            let mut container = M8Container::from_text(&"x".repeat(i + 1), 5, mem8.clone()).unwrap();
+            if i % 2 == 0 {
+                container.header.metadata.insert("tags".to_string(), "even, small".to_string());
+            }
+            nexus.store(container);
+        }
+        
+        // Walk every page smallest first
+        let mut query = M8Query { sort: M8Sort::Size, limit: 2, ..Default::default() };
+        let mut sizes = Vec::new();
+        loop {
+            let page = nexus.page(&query);
+            sizes.extend(page.containers.iter().map(|c| c.data.len()));
+            match page.next {
+                Some(next) => query.after = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(sizes, vec![1, 2, 3, 4, 5]);
+        
+        let query = M8Query { sort: M8Sort::Size, descending: true, limit: 10, tag: Some("even".to_string()), ..Default::default() };
+        let page = nexus.page(&query);
+        assert_eq!(page.containers.iter().map(|c| c.data.len()).collect::<Vec<_>>(), vec![5, 3, 1]);
+        assert!(page.next.is_none());
+        
+        let query = M8Query { limit: 10, content_type: Some("visual".to_string()), ..Default::default() };
+        assert!(nexus.page(&query).containers.is_empty());
+    }
+    
+    #[test]
+    fn test_nexus_memory_index() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        let start = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000);
+        
+        let mut first = M8Container::from_text("first words", 7, mem8.clone()).unwrap();
+        first.header.memory_ids = vec![1];
+        first.header.timestamp = start;
+        let first_sig = nexus.store(first);
+        let mut later = M8Container::from_text("later words", 3, mem8.clone()).unwrap();
+        later.header.memory_ids = vec![2];
+        later.header.timestamp = start + std::time::Duration::from_secs(10);
+        nexus.store(later);
+        let mut imported = M8Container::from_text("not ours", 5, mem8).unwrap();
+        imported.header.memory_ids = vec![3];
+        nexus.store_import(imported);
+        
+        let memory = nexus.memory(1).unwrap();
+        assert_eq!((memory.content.as_str(), memory.importance), ("first words", Some(7)));
+        assert!(nexus.memory(3).is_none());
+        assert_eq!(nexus.latest_language_memory().unwrap().0, 2);
+        
+        let ids = |until| nexus.memories_between(start, until).map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(ids(None), vec![1, 2]);
+        assert_eq!(ids(Some(start)), vec![1]);
+        assert!(nexus.memories_between(start, Some(start - std::time::Duration::from_secs(1))).next().is_none());
+        
+        // MEM8 keeps the memory after its container goes, and so does the record
+        nexus.remove(&first_sig);
+        assert_eq!(nexus.memory(1).unwrap().content, "first words");
+    }
+    
+    #[test]
+    fn test_nexus_type_stats() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let mut nexus = M8Nexus::new();
+        let text = "wave ".repeat(200);
+        nexus.store(M8Container::from_text(&text, 5, mem8.clone()).unwrap());
+        nexus.store(M8Container::from_text("tiny", 5, mem8.clone()).unwrap());
+        let marqant = Marqant::from_markdown("# Notes\n\nShort and sweet.").unwrap();
+        let notes = nexus.store(M8Container::from_marqant(&marqant, mem8).unwrap());
+        assert_eq!(nexus.type_stats()["Marqant"].original_bytes, marqant.header.original_size);
+        
+        // Totals follow removals, and a type with nothing left drops out
+        nexus.remove(&notes);
+        assert!(!nexus.type_stats().contains_key("Marqant"));
+        
+        let stats = nexus.type_stats();
+        let text_stats = &stats["Language"];
+        assert_eq!(text_stats.containers, 2);
+        assert_eq!(text_stats.original_bytes, text.len() as u64 + 4);
+        assert!(text_stats.stored_bytes < text_stats.original_bytes);
+        assert_eq!(nexus.container_count(), 2);
+        assert_eq!(nexus.referenced_memories(), 2);
     }
 }
\ No newline at end of file
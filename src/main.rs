@@ -18,20 +18,36 @@ use tracing_subscriber::EnvFilter;
 use std::sync::{Arc, Mutex};
 
 mod api;
+mod audio;
+mod hex;
+mod idempotency;
+mod ingest;
+mod jobs;
+mod live_feed;
 mod markqant;
+mod mime;
 mod m8;
 mod consent;
+mod emotion;
+mod error;
+mod events;
 mod auctioneer;
 mod auctioneer_battles;
+mod retention;
+mod visual;
 
 // Import Mem8 and M8Nexus
 use mem8::Mem8;
 use crate::m8::M8Nexus;
 
-// Import EventQueue for SSE
-use crate::api::EventQueue;
-use std::collections::VecDeque;
+// Import the event hub for SSE
+use crate::events::{EventHub, SharedEventHub};
 use crate::auctioneer::Auctioneer;
+use crate::idempotency::{IdempotencyCache, SharedIdempotencyCache};
+use crate::ingest::{Ingestor, SharedIngestor};
+use crate::jobs::{JobQueue, SharedJobQueue};
+use crate::live_feed::{LiveFeed, SharedLiveFeed};
+use crate::retention::{RetentionPolicy, SharedRetentionPolicy};
 use tokio::sync::mpsc;
 
 #[actix_web::main]
@@ -53,40 +69,62 @@ async fn main() -> std::io::Result<()> {
     ));
     
     // Create the M8 nexus for container management
-    let nexus = Arc::new(Mutex::new(M8Nexus::new(mem8.clone())));
+    let nexus = Arc::new(Mutex::new(M8Nexus::new()));
     
-    // Create event queue for real-time updates
-    let event_queue: EventQueue = Arc::new(Mutex::new(VecDeque::new()));
+    // Create the event hub for real-time updates
+    let events: SharedEventHub = Arc::new(EventHub::default());
+    
+    // Retention policy (per-content-type TTLs) and the expiry sweeper
+    let retention: SharedRetentionPolicy = Arc::new(Mutex::new(RetentionPolicy::default()));
+    tokio::spawn(retention::run_sweeper(nexus.clone(), events.clone()));
+    
+    // Remembered upload responses for Idempotency-Key retries
+    let idempotency: SharedIdempotencyCache = Arc::new(IdempotencyCache::default());
     
     // Create auctioneer channel and instance
-    let (auctioneer_tx, mut auctioneer_rx) = mpsc::unbounded_channel();
-    let auctioneer = Arc::new(Auctioneer::new(auctioneer_tx.clone()));
+    let (auctioneer_tx, auctioneer_rx) = mpsc::unbounded_channel();
+    let auctioneer = Arc::new(Auctioneer::new(auctioneer_tx));
     
-    // Spawn auctioneer event broadcaster
-    tokio::spawn(async move {
-        while let Some(event) = auctioneer_rx.recv().await {
-            // Process events and broadcast to connected clients
-            tracing::debug!("Auctioneer event: {:?}", event);
-        }
+    // One ingest pipeline shared by every upload route
+    let ingestor: SharedIngestor = Arc::new(Ingestor {
+        mem8: mem8.clone(),
+        nexus: nexus.clone(),
+        retention: retention.clone(),
+        events: events.clone(),
+        auctioneer: auctioneer.clone(),
     });
     
+    // Background queue for `?async=true` uploads
+    let jobs: SharedJobQueue = Arc::new(JobQueue::new(jobs::MAX_CONCURRENT_JOBS, events.clone()));
+    
+    // Fan auctioneer events out to every /auctioneer/live session
+    let feed: SharedLiveFeed = Arc::new(LiveFeed::default());
+    tokio::spawn(live_feed::run(feed.clone(), auctioneer_rx));
+    
     // Log startup info
     tracing::info!("MEM8 quantum brain initialized");
     tracing::info!("M8 nexus container system ready");
     tracing::info!("Auctioneer live feed ready");
+    tracing::info!("Retention sweeper running every {}s", retention::SWEEP_INTERVAL.as_secs());
     tracing::info!("Starting server on http://127.0.0.1:8420");
     
     HttpServer::new(move || {
         App::new()
+            // Tag every request with an ID (echoed in X-Request-Id and error bodies)
+            .wrap_fn(crate::error::with_request_id)
             // Add logging middleware
             .wrap(middleware::Logger::default())
             
             // Share quantum components with all routes
             .app_data(web::Data::new(mem8.clone()))
             .app_data(web::Data::new(nexus.clone()))
-            .app_data(web::Data::new(event_queue.clone()))
+            .app_data(web::Data::new(events.clone()))
+            .app_data(web::Data::new(retention.clone()))
+            .app_data(web::Data::new(idempotency.clone()))
+            .app_data(web::Data::new(ingestor.clone()))
+            .app_data(web::Data::new(jobs.clone()))
             .app_data(web::Data::new(auctioneer.clone()))
-            .app_data(web::Data::new(auctioneer_tx.clone()))
+            .app_data(web::Data::new(feed.clone()))
             
             // Configure all routes
             .configure(api::init_routes)
@@ -100,16 +138,43 @@ async fn main() -> std::io::Result<()> {
 Welcome to the quantum-compressed consciousness nexus!
 
 Available endpoints:
-- POST /upload - Auto-detect and upload files (.mq, .m8, or text)
+- POST /upload - Auto-detect and upload files (.mq, .m8, PNG/JPEG, WAV, text, or binary blobs)
 - POST /upload/marqant - Upload Marqant files
 - POST /upload/text - Upload plain text
+- POST /upload/wave - Upload explicit wave parameters (JSON or M8WV binary)
+- POST /upload/batch - Bulk ingest NDJSON records, streaming back NDJSON results
+  (all uploads accept ?parent={signature} to record a revision and ?ttl={seconds})
+  (and ?valence=&arousal=&dominance= to override the inferred emotional context)
+  (multipart uploads take several files; ?bind=true binds them into a compound)
+  (?async=true queues the upload as a background job and answers 202)
+- GET /jobs/{id} - Status and result of a background upload
 - GET /container/{signature} - Retrieve container by wave signature
-- GET /containers - List all containers
+  (?format=m8|mq|markdown|json, or the Accept header, picks the representation)
+- DELETE /container/{signature} - Delete a container (lists MEM8 memories left unreferenced)
+- GET /container/{signature}/ancestors - Walk a container's parent chain
+- GET /container/{signature}/descendants - List later revisions
+- GET /container/{signature}/history - Version history as a DAG
+- GET /container/{signature}/wave - Wave payload as JSON (or ?format=raw)
+- GET /container/{signature}/meta - Full container header and sizes as JSON
+- PATCH /container/{signature}/meta - Edit user metadata (signature unchanged)
+- GET /containers - List containers, paginated (?sort=&order=&cursor=&limit=)
+  (filter with ?content_type=, ?since=, ?until=, ?meta=key:value, ?tag=)
+- GET /visual/similar/{signature} - Find images by perceptual-hash similarity
+- POST /bind - Bind containers/memories into a compound with an emotional context
+- GET /container/{signature}/unpack - Members and wave patterns of a compound
+- GET /retention - Retention policy and upcoming container expiries
+- PUT /retention/policy - Set the TTL inherited by a content type
 - GET /mem8/stats - Get nexus and MEM8 statistics
 - GET /mem8/context/latest - Get latest language memory
+- GET /mem8/memories - Memories stored in a time window (?since=&until=&limit=)
+- GET /mem8/memory/{id} - One memory: content, importance, wave pattern, timestamp
+- GET /mem8/memory/{id}/containers - Containers referencing a memory
 - GET /events - Server-sent events for real-time updates
 - GET /static/auctioneer.html - 🎪 LIVE AUCTIONEER FEED! Watch AI battles & contributions!
 - WS /auctioneer/live - WebSocket connection for real-time commentary
+  (send "/style {fast|dramatic|technical|comedic|philosophical}" to pick your own style)
+- GET/PUT /auctioneer/style - Read or change the global commentary style
+- POST /battle/events - Send an AI coding battle event, get the commentary back
 
 Marqants (.mq) are quantum-compressed markdown achieving massive compression.
 M8 containers (.m8) store wave-based memory patterns with cross-sensory bindings.
@@ -131,8 +196,7 @@ mod tests {
     #[test]
     fn test_server_creation() {
         // Test that we can create the necessary components
-        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
-        let nexus = Arc::new(Mutex::new(M8Nexus::new(mem8.clone())));
+        let nexus = Arc::new(Mutex::new(M8Nexus::new()));
         // Verify the nexus is created properly
         let stats = nexus.lock().unwrap().stats();
         assert!(stats.is_empty()); // Should have no containers initially
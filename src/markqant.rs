@@ -6,7 +6,7 @@
 //! while achieving massive compression through wave-based encoding.
 
 // use mem8::{WavePattern};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
@@ -18,6 +18,9 @@ use anyhow::Result;
 pub const MQ_MAGIC: &[u8] = b"MQ03"; // Version 3 of Marqant format
 
 /// Marqant header structure
+///
+/// Maps are ordered so `to_bytes` is deterministic: the same markdown always
+/// yields the same bytes, and therefore the same container wave signature.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarqantHeader {
     pub version: u8,
@@ -25,7 +28,7 @@ pub struct MarqantHeader {
     pub original_size: u64,
     pub compressed_size: u64,
     pub wave_signature: [u8; 32], // SHA256 of wave pattern
-    pub metadata: HashMap<String, String>,
+    pub metadata: BTreeMap<String, String>,
 }
 
 /// Marqant container - holds quantum-compressed markdown
@@ -33,7 +36,7 @@ pub struct MarqantHeader {
 pub struct Marqant {
     pub header: MarqantHeader,
     pub wave_data: Vec<u8>,
-    pub semantic_map: HashMap<String, Vec<u32>>, // Semantic tokens to wave indices
+    pub semantic_map: BTreeMap<String, Vec<u32>>, // Semantic tokens to wave indices
 }
 
 impl Marqant {
@@ -59,7 +62,7 @@ impl Marqant {
             original_size: markdown.len() as u64,
             compressed_size: compressed_wave.len() as u64,
             wave_signature,
-            metadata: HashMap::from([
+            metadata: BTreeMap::from([
                 ("format".to_string(), "marqant".to_string()),
                 ("encoding".to_string(), "quantum-wave".to_string()),
             ]),
@@ -84,8 +87,8 @@ impl Marqant {
     }
     
     /// Extract semantic tokens from markdown
-    fn extract_semantic_tokens(markdown: &str) -> HashMap<String, Vec<u32>> {
-        let mut semantic_map = HashMap::new();
+    fn extract_semantic_tokens(markdown: &str) -> BTreeMap<String, Vec<u32>> {
+        let mut semantic_map = BTreeMap::new();
         let mut position = 0u32;
         
         // Extract headers
@@ -120,7 +123,7 @@ impl Marqant {
     }
     
     /// Convert markdown to quantum wave pattern
-    fn markdown_to_wave(markdown: &str, _semantic_map: &HashMap<String, Vec<u32>>) -> Result<Vec<u8>> {
+    fn markdown_to_wave(markdown: &str, _semantic_map: &BTreeMap<String, Vec<u32>>) -> Result<Vec<u8>> {
         // For now, use a simple byte representation
         // In a full implementation, this would use quantum encoding
         // based on semantic understanding and wave interference patterns
@@ -128,7 +131,7 @@ impl Marqant {
     }
     
     /// Convert wave pattern back to markdown
-    fn wave_to_markdown(wave_data: &[u8], _semantic_map: &HashMap<String, Vec<u32>>) -> Result<String> {
+    fn wave_to_markdown(wave_data: &[u8], _semantic_map: &BTreeMap<String, Vec<u32>>) -> Result<String> {
         // For now, simple conversion
         // Full implementation would reconstruct using semantic map
         Ok(String::from_utf8_lossy(wave_data).to_string())
@@ -167,28 +170,13 @@ impl Marqant {
     
     /// Deserialize from .mq format
     pub fn from_bytes(data: &[u8]) -> Result<Self> {
-        if data.len() < 4 || &data[0..4] != MQ_MAGIC {
-            return Err(anyhow::anyhow!("Invalid Marqant file format"));
-        }
-        
-        let mut cursor = 4;
-        
-        // Read header
-        let header_len = u32::from_le_bytes([
-            data[cursor], data[cursor+1], data[cursor+2], data[cursor+3]
-        ]) as usize;
-        cursor += 4;
-        let header: MarqantHeader = bincode::deserialize(&data[cursor..cursor+header_len])?;
-        cursor += header_len;
+        let mut cursor = 0;
+        let header = read_header_at(data, &mut cursor)?;
         
         // Read semantic map
-        let semantic_len = u32::from_le_bytes([
-            data[cursor], data[cursor+1], data[cursor+2], data[cursor+3]
-        ]) as usize;
-        cursor += 4;
-        let semantic_map: HashMap<String, Vec<u32>> = 
-            bincode::deserialize(&data[cursor..cursor+semantic_len])?;
-        cursor += semantic_len;
+        let semantic_len = read_u32(data, &mut cursor)? as usize;
+        let semantic_map: BTreeMap<String, Vec<u32>> = 
+            bincode::deserialize(take(data, &mut cursor, semantic_len)?)?;
         
         // Remaining data is wave data
         let wave_data = data[cursor..].to_vec();
@@ -200,12 +188,41 @@ impl Marqant {
         })
     }
     
+    /// Just the header of a .mq file, without decoding the rest
+    pub fn read_header(data: &[u8]) -> Result<MarqantHeader> {
+        read_header_at(data, &mut 0)
+    }
+    
     /// Get compression ratio
     pub fn compression_ratio(&self) -> f64 {
         self.header.original_size as f64 / self.header.compressed_size as f64
     }
 }
 
+/// Check the magic bytes and read the header, leaving `cursor` just past it
+fn read_header_at(data: &[u8], cursor: &mut usize) -> Result<MarqantHeader> {
+    if data.len() < 4 || &data[0..4] != MQ_MAGIC {
+        return Err(anyhow::anyhow!("Invalid Marqant file format"));
+    }
+    *cursor = 4;
+    let header_len = read_u32(data, cursor)? as usize;
+    Ok(bincode::deserialize(take(data, cursor, header_len)?)?)
+}
+
+/// The next `len` bytes after `cursor`, or an error if the file ends first
+fn take<'a>(data: &'a [u8], cursor: &mut usize, len: usize) -> Result<&'a [u8]> {
+    let bytes = cursor.checked_add(len)
+        .and_then(|end| data.get(*cursor..end))
+        .ok_or_else(|| anyhow::anyhow!("Truncated Marqant file"))?;
+    *cursor += len;
+    Ok(bytes)
+}
+
+/// Little-endian `u32` at `cursor`
+fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(take(data, cursor, 4)?.try_into()?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,4 +250,20 @@ fn main() {
         assert_eq!(markdown, recovered);
         println!("Compression ratio: {:.2}x", marqant.compression_ratio());
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_truncated_marqant_is_rejected() {
+        let marqant = Marqant::from_markdown("# Short\n\nBut complete.").unwrap();
+        let bytes = marqant.to_bytes().unwrap();
+        assert!(Marqant::from_bytes(&bytes).is_ok());
+        
+        // Every prefix that cuts into the header or semantic map is an error, not a panic
+        assert!(Marqant::from_bytes(b"MQ03").is_err());
+        for len in 4..bytes.len() - marqant.wave_data.len() {
+            assert!(Marqant::from_bytes(&bytes[..len]).is_err(), "prefix of {} bytes", len);
+        }
+        let mut bad_len = MQ_MAGIC.to_vec();
+        bad_len.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Marqant::from_bytes(&bad_len).is_err());
+    }
+}
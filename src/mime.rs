@@ -0,0 +1,135 @@
+//! MIME type sniffing for uploads
+//!
+//! "Never judge a file by its extension. Ask its magic bytes." - Trish
+//!
+//! Magic bytes win; the filename extension is only consulted when the
+//! content itself doesn't say what it is.
+
+use std::path::Path;
+
+pub const MARQANT_MIME: &str = "application/x-marqant";
+pub const M8_MIME: &str = "application/x-m8";
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Known signatures: (offset, magic bytes, MIME type)
+const MAGIC: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, &[0xFF, 0xD8, 0xFF], "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, &[0x1F, 0x8B], "application/gzip"),
+    (0, b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"\x00asm", "application/wasm"),
+    (0, b"\x7FELF", "application/x-elf"),
+    (0, b"MQ03", MARQANT_MIME),
+    (0, b"M8C1", M8_MIME),
+    (4, b"ftyp", "video/mp4"),
+];
+
+/// Extension fallbacks for content without recognisable magic bytes
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("mq", MARQANT_MIME),
+    ("m8", M8_MIME),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("json", "application/json"),
+    ("csv", "text/csv"),
+    ("html", "text/html"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("wav", "audio/wav"),
+    ("mp3", "audio/mpeg"),
+    ("bin", OCTET_STREAM),
+];
+
+/// Best guess at the MIME type of an upload
+pub fn sniff(bytes: &[u8], filename: &str) -> &'static str {
+    // RIFF is a container; the form type says what's inside
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" {
+        match &bytes[8..12] {
+            b"WAVE" => return "audio/wav",
+            b"WEBP" => return "image/webp",
+            _ => {}
+        }
+    }
+    // Two or three letters are too easy to hit in plain text ("BMW", "ID3 tags"),
+    // so these only count with the header fields that follow them
+    if is_bmp(bytes) {
+        return "image/bmp";
+    }
+    if is_id3(bytes) {
+        return "audio/mpeg";
+    }
+    if let Some((_, _, mime)) = MAGIC.iter()
+        .find(|(offset, magic, _)| bytes.get(*offset..offset + magic.len()) == Some(*magic))
+    {
+        return mime;
+    }
+
+    let ext = Path::new(filename).extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    if let Some((_, mime)) = ext.and_then(|ext| EXTENSIONS.iter().find(|(e, _)| *e == ext)) {
+        return mime;
+    }
+
+    if is_text(bytes) { "text/plain" } else { OCTET_STREAM }
+}
+
+/// "BM", then a BITMAPFILEHEADER with zeroed reserved words and a known DIB header size
+fn is_bmp(bytes: &[u8]) -> bool {
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    bytes.starts_with(b"BM")
+        && u32_at(6) == Some(0)
+        && matches!(u32_at(14), Some(12 | 40 | 52 | 56 | 64 | 108 | 124))
+}
+
+/// "ID3", an ID3v2.2-2.4 version, and a syncsafe tag size (high bit clear in every byte)
+fn is_id3(bytes: &[u8]) -> bool {
+    match bytes.get(0..10) {
+        Some([b'I', b'D', b'3', major, minor, _flags, size @ ..]) => {
+            (2..=4).contains(major) && *minor != 0xFF && size.iter().all(|b| b & 0x80 == 0)
+        }
+        _ => false,
+    }
+}
+
+/// Valid UTF-8 without control bytes other than whitespace
+pub fn is_text(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).is_ok_and(|text| {
+        !text.chars().any(|c| c.is_control() && !c.is_whitespace())
+    })
+}
+
+/// MIME types whose content is stored as a Language container
+pub fn is_textual(mime: &str) -> bool {
+    mime.starts_with("text/") || matches!(mime, "application/json" | "application/xml" | "image/svg+xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_magic_before_extension() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n....", "photo.txt"), "image/png");
+        assert_eq!(sniff(b"RIFF\0\0\0\0WAVEfmt ", "clip.bin"), "audio/wav");
+        assert_eq!(sniff(b"%PDF-1.7", "upload.bin"), "application/pdf");
+        assert_eq!(sniff(b"# Title", "notes.md"), "text/markdown");
+        assert_eq!(sniff(b"hello", "upload.bin"), OCTET_STREAM);
+        assert_eq!(sniff(b"hello", "noext"), "text/plain");
+        assert_eq!(sniff(&[0x00, 0x9F, 0x92, 0x96], "noext"), OCTET_STREAM);
+
+        // Weak magics need their headers; text that merely starts with them stays text
+        assert_eq!(sniff(b"BMW service due in March", "notes.txt"), "text/plain");
+        assert_eq!(sniff(b"ID3 tags are a mess", "notes.md"), "text/markdown");
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&[0x46, 0, 0, 0, 0, 0, 0, 0, 0x36, 0, 0, 0, 40, 0, 0, 0]);
+        assert_eq!(sniff(&bmp, "image.txt"), "image/bmp");
+        assert_eq!(sniff(b"ID3\x04\x00\x00\x00\x00\x01\x7f", "song.bin"), "audio/mpeg");
+    }
+}
@@ -0,0 +1,151 @@
+//! Retention policies and TTL expiry for M8 containers
+//!
+//! "Even quantum memories deserve a graceful goodbye." - Trish
+//!
+//! Each container may carry an `expires_at` metadata entry (unix seconds),
+//! set explicitly at upload or inherited from a per-content-type TTL.
+//! A background sweeper removes expired containers from the nexus.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Serialize, Deserialize};
+
+use crate::events::{NexusEvent, SharedEventHub};
+use crate::hex;
+use crate::m8::{M8Container, M8ContentType, M8Nexus, EXPIRES_AT_KEY};
+
+/// How often the sweeper looks for expired containers
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Per-content-type time-to-live defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub ttls: HashMap<String, u64>, // Content type name -> TTL in seconds
+}
+
+/// Shared, editable retention policy
+pub type SharedRetentionPolicy = Arc<Mutex<RetentionPolicy>>;
+
+impl RetentionPolicy {
+    /// TTL inherited by containers of the given content type
+    pub fn ttl_for(&self, container: &M8Container) -> Option<Duration> {
+        let type_name = format!("{:?}", container.header.content_type);
+        self.ttls.get(&type_name).map(|secs| Duration::from_secs(*secs))
+    }
+
+    /// Set or clear the TTL for a content type (its exact name, e.g. "Language")
+    pub fn set_ttl(&mut self, content_type: String, ttl: Option<u64>) -> Result<(), String> {
+        if !M8ContentType::ALL.iter().any(|t| format!("{:?}", t) == content_type) {
+            let names: Vec<_> = M8ContentType::ALL.iter().map(|t| format!("{:?}", t)).collect();
+            return Err(format!("Unknown content type '{}'; expected one of {}", content_type, names.join(", ")));
+        }
+        match ttl {
+            Some(secs) => {
+                expiry_after(Duration::from_secs(secs))?;
+                self.ttls.insert(content_type, secs);
+            }
+            None => {
+                self.ttls.remove(&content_type);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stamp `expires_at` on a freshly built container.
+    /// An explicit TTL wins over the content-type policy; no TTL means keep forever.
+    pub fn apply(&self, container: &mut M8Container, explicit_ttl: Option<Duration>) -> Result<(), String> {
+        let Some(ttl) = explicit_ttl.or_else(|| self.ttl_for(container)) else {
+            return Ok(());
+        };
+        let secs = expiry_after(ttl)?.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        container.header.metadata.insert(EXPIRES_AT_KEY.to_string(), secs.to_string());
+        Ok(())
+    }
+}
+
+/// When something with this TTL expires, if that is a time we can represent
+pub fn expiry_after(ttl: Duration) -> Result<SystemTime, String> {
+    SystemTime::now().checked_add(ttl)
+        .ok_or_else(|| format!("TTL of {}s is too long", ttl.as_secs()))
+}
+
+/// Remove every expired container, returning the removed signatures
+pub fn sweep(nexus: &Arc<Mutex<M8Nexus>>, events: &SharedEventHub) -> Vec<[u8; 32]> {
+    let now = SystemTime::now();
+    let removed: Vec<_> = {
+        let mut nexus_lock = nexus.lock().unwrap();
+        nexus_lock.expired(now)
+            .into_iter()
+            .filter_map(|sig| nexus_lock.remove(&sig))
+            .collect()
+    };
+
+    for removal in &removed {
+        events.publish(NexusEvent::ContainerExpired {
+            signature: hex::encode(removal.container.wave_signature),
+            content_type: removal.container.header.content_type.clone(),
+            unreferenced_memory_ids: removal.unreferenced_memory_ids.clone(),
+        });
+    }
+
+    removed.into_iter().map(|r| r.container.wave_signature).collect()
+}
+
+/// Background task: sweep expired containers forever
+pub async fn run_sweeper(nexus: Arc<Mutex<M8Nexus>>, events: SharedEventHub) {
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        let expired = sweep(&nexus, &events);
+        if !expired.is_empty() {
+            tracing::info!("Retention sweeper expired {} containers", expired.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventHub;
+    use mem8::Mem8;
+
+    #[test]
+    fn test_policy_and_sweep() {
+        let mem8 = Arc::new(Mutex::new(Mem8::new_default().unwrap()));
+        let nexus = Arc::new(Mutex::new(M8Nexus::new()));
+        let events: SharedEventHub = Arc::new(EventHub::default());
+
+        let policy = RetentionPolicy {
+            ttls: HashMap::from([("Language".to_string(), 3600)]),
+        };
+
+        let mut inherited = M8Container::from_text("fleeting", 5, mem8.clone()).unwrap();
+        policy.apply(&mut inherited, None).unwrap();
+        assert!(inherited.expires_at().unwrap() > SystemTime::now());
+
+        // TTLs past the end of time are refused rather than overflowing
+        let mut forever = M8Container::from_text("forever", 5, mem8.clone()).unwrap();
+        assert!(policy.apply(&mut forever, Some(Duration::from_secs(u64::MAX))).is_err());
+        forever.header.metadata.insert(EXPIRES_AT_KEY.to_string(), u64::MAX.to_string());
+        assert!(forever.expires_at().is_none());
+
+        // Policies only name real content types, spelled exactly
+        let mut edited = policy.clone();
+        assert!(edited.set_ttl("language".to_string(), Some(60)).is_err());
+        assert!(edited.set_ttl("Blob".to_string(), Some(u64::MAX)).is_err());
+        assert!(edited.set_ttl("Blob".to_string(), Some(60)).is_ok());
+        assert!(edited.set_ttl("Language".to_string(), None).is_ok());
+        assert_eq!(edited.ttls, HashMap::from([("Blob".to_string(), 60)]));
+
+        // An already-elapsed explicit TTL expires on the next sweep
+        let mut doomed = M8Container::from_text("gone", 5, mem8).unwrap();
+        doomed.header.metadata.insert(EXPIRES_AT_KEY.to_string(), "1".to_string());
+        let doomed_sig = nexus.lock().unwrap().store(doomed);
+        let kept_sig = nexus.lock().unwrap().store(inherited);
+
+        assert_eq!(sweep(&nexus, &events), vec![doomed_sig]);
+        assert!(nexus.lock().unwrap().retrieve(&kept_sig).is_some());
+        assert_eq!(events.recent().len(), 1);
+    }
+}
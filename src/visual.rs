@@ -0,0 +1,185 @@
+//! Visual memory ingestion for PNG/JPEG images
+//!
+//! "A picture is worth a thousand waves." - Hue
+//!
+//! Everything here is computed locally: dimensions, a small palette of
+//! dominant colors and a DCT-based perceptual hash. Images that look alike
+//! have perceptual hashes a small Hamming distance apart.
+
+use anyhow::Result;
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageFormat};
+use std::collections::HashMap;
+
+/// Features extracted from an uploaded image
+#[derive(Debug, Clone)]
+pub struct ImageFeatures {
+    pub format: &'static str, // "png" or "jpeg"
+    pub width: u32,
+    pub height: u32,
+    pub dominant_colors: Vec<[u8; 3]>, // Most common first
+    pub phash: u64,
+    pub mean_luminance: f32, // 0-1
+}
+
+impl ImageFeatures {
+    /// MIME type of the original image bytes
+    pub fn mime(&self) -> &'static str {
+        match self.format {
+            "png" => "image/png",
+            _ => "image/jpeg",
+        }
+    }
+
+    /// Wave parameters used to register the image in MEM8: brightness drives
+    /// amplitude, detail (set bits of the perceptual hash) drives frequency,
+    /// and the hue of the dominant color sets the phase
+    pub fn wave_parameters(&self) -> (f32, f32, f32) {
+        let amplitude = self.mean_luminance;
+        let frequency = self.phash.count_ones() as f32 / 64.0;
+        let phase = self.dominant_colors.first().map(hue_radians).unwrap_or(0.0);
+        (amplitude, frequency, phase)
+    }
+}
+
+/// Hue of an RGB color in radians [0, 2π)
+fn hue_radians(color: &[u8; 3]) -> f32 {
+    let [r, g, b] = color.map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    if delta == 0.0 {
+        return 0.0;
+    }
+    let sector = if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    sector * std::f32::consts::PI / 3.0
+}
+
+/// Decode a PNG/JPEG and extract its features
+pub fn analyze(bytes: &[u8]) -> Result<ImageFeatures> {
+    let format = match image::guess_format(bytes)? {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpeg",
+        other => return Err(anyhow::anyhow!("Unsupported image format: {:?}", other)),
+    };
+    let img = image::load_from_memory(bytes)?;
+    let (width, height) = img.dimensions();
+
+    let gray = img.resize_exact(32, 32, FilterType::Triangle).to_luma8();
+    let mean_luminance = gray.pixels().map(|p| p.0[0] as f32).sum::<f32>() / (32.0 * 32.0 * 255.0);
+
+    Ok(ImageFeatures {
+        format,
+        width,
+        height,
+        dominant_colors: dominant_colors(&img, 3),
+        phash: perceptual_hash(&gray),
+        mean_luminance,
+    })
+}
+
+/// Most common colors, bucketed to 4 levels per channel and averaged per bucket
+fn dominant_colors(img: &DynamicImage, count: usize) -> Vec<[u8; 3]> {
+    let small = img.resize(64, 64, FilterType::Triangle).to_rgb8();
+    let mut buckets: HashMap<u8, (u32, [u32; 3])> = HashMap::new();
+    for pixel in small.pixels() {
+        let [r, g, b] = pixel.0;
+        let key = (r >> 6) << 4 | (g >> 6) << 2 | (b >> 6);
+        let bucket = buckets.entry(key).or_insert((0, [0; 3]));
+        bucket.0 += 1;
+        bucket.1[0] += r as u32;
+        bucket.1[1] += g as u32;
+        bucket.1[2] += b as u32;
+    }
+
+    let mut ranked: Vec<_> = buckets.into_iter().collect();
+    ranked.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.0.cmp(&b.0)));
+    ranked.into_iter()
+        .take(count)
+        .map(|(_, (n, sums))| sums.map(|sum| (sum / n) as u8))
+        .collect()
+}
+
+/// pHash: 2D DCT of a 32x32 grayscale image, keep the 8x8 low frequencies
+/// (minus the DC term) and set a bit for every coefficient above the median
+fn perceptual_hash(gray: &image::GrayImage) -> u64 {
+    const N: usize = 32;
+    let pixels: Vec<f32> = gray.pixels().map(|p| p.0[0] as f32).collect();
+    let cosines: Vec<f32> = (0..8)
+        .flat_map(|u| {
+            (0..N).map(move |x| {
+                (((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI) / (2.0 * N as f32)).cos()
+            })
+        })
+        .collect();
+
+    let mut coefficients = Vec::with_capacity(63);
+    for v in 0..8 {
+        for u in 0..8 {
+            if u == 0 && v == 0 {
+                continue;
+            }
+            let mut sum = 0.0;
+            for y in 0..N {
+                for x in 0..N {
+                    sum += pixels[y * N + x] * cosines[u * N + x] * cosines[v * N + y];
+                }
+            }
+            coefficients.push(sum);
+        }
+    }
+
+    let mut sorted = coefficients.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+    coefficients.iter()
+        .enumerate()
+        .filter(|(_, c)| **c > median)
+        .fold(0u64, |hash, (i, _)| hash | 1 << i)
+}
+
+/// Number of differing bits between two perceptual hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Format a color as `#rrggbb`
+pub fn color_hex(color: &[u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+    use std::io::Cursor;
+
+    fn png_bytes(img: ImageBuffer<Rgb<u8>, Vec<u8>>) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(img).write_to(&mut out, ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_analyze_png() {
+        let gradient = ImageBuffer::from_fn(40, 20, |x, _| Rgb([(x * 6) as u8, 0, 200]));
+        let bytes = png_bytes(gradient.clone());
+        assert_eq!(crate::mime::sniff(&bytes, "gradient"), "image/png");
+
+        let features = analyze(&bytes).unwrap();
+        assert_eq!((features.width, features.height), (40, 20));
+        assert_eq!(features.mime(), "image/png");
+        assert!(!features.dominant_colors.is_empty());
+
+        // A slightly brighter copy hashes nearly the same; an inverted one doesn't
+        let brighter = ImageBuffer::from_fn(40, 20, |x, _| Rgb([(x * 6 + 5) as u8, 5, 205]));
+        let inverted = ImageBuffer::from_fn(40, 20, |x, _| Rgb([255 - (x * 6) as u8, 0, 200]));
+        let near = analyze(&png_bytes(brighter)).unwrap().phash;
+        let far = analyze(&png_bytes(inverted)).unwrap().phash;
+        assert!(hamming_distance(features.phash, near) < hamming_distance(features.phash, far));
+    }
+}